@@ -0,0 +1,280 @@
+use crate::model::ToolSpec;
+use crate::tool::{Tool, ToolError, ToolProgress};
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt as _, BufReader};
+use tokio::process::Command;
+
+/// The observations of the tools are cut to this many characters.
+const MAX_OUTPUT_CHARS: usize = 30_000;
+
+/// Returns the tools of a coding partner working in `root`: reading, listing and writing
+/// the files of the project and running shell commands in it.
+pub fn project_tools(root: &Path) -> Vec<Arc<dyn Tool>> {
+    vec![
+        Arc::new(ReadFile::new(root)),
+        Arc::new(ListFiles::new(root)),
+        Arc::new(WriteFile::new(root)),
+        Arc::new(Shell::new(root)),
+    ]
+}
+
+/// Returns a string argument, failing if it is missing.
+fn required<'a>(arguments: &'a serde_json::Value, name: &str) -> Result<&'a str, ToolError> {
+    arguments[name]
+        .as_str()
+        .ok_or_else(|| ToolError::InvalidArguments(format!("{} is required", name)))
+}
+
+/// Resolves a path of the project, relative to `root` and not leaving it.
+fn resolve(root: &Path, path: &str) -> Result<PathBuf, ToolError> {
+    let relative = Path::new(path);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(ToolError::InvalidArguments(format!(
+            "{} is not a relative path inside the project",
+            path
+        )));
+    }
+    Ok(root.join(relative))
+}
+
+/// Cuts a long output, keeping its start and telling how much was left out.
+fn truncate(mut output: String) -> String {
+    if let Some((index, _)) = output.char_indices().nth(MAX_OUTPUT_CHARS) {
+        let omitted = output[index..].chars().count();
+        output.truncate(index);
+        output.push_str(&format!("\n... ({} more characters)", omitted));
+    }
+    output
+}
+
+/// Reads a text file of the project.
+pub struct ReadFile {
+    root: PathBuf,
+}
+
+impl ReadFile {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl Tool for ReadFile {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "read_file".to_string(),
+            description: "Returns the content of a text file, the path is relative to the \
+                          project root"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {"path": {"type": "string"}},
+                "required": ["path"]
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<String, ToolError> {
+        let path = required(&arguments, "path")?;
+        let content = tokio::fs::read_to_string(resolve(&self.root, path)?)
+            .await
+            .map_err(|err| ToolError::Failed(format!("{}: {}", path, err)))?;
+        Ok(truncate(content))
+    }
+}
+
+/// Lists a directory of the project, the subdirectories end with `/`.
+pub struct ListFiles {
+    root: PathBuf,
+}
+
+impl ListFiles {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl Tool for ListFiles {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "list_files".to_string(),
+            description: "Lists a directory, the path is relative to the project root and \
+                          defaults to the root itself"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {"path": {"type": "string"}}
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<String, ToolError> {
+        let path = arguments["path"].as_str().unwrap_or(".");
+        let failed = |err: std::io::Error| ToolError::Failed(format!("{}: {}", path, err));
+        let mut entries = tokio::fs::read_dir(resolve(&self.root, path)?)
+            .await
+            .map_err(failed)?;
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(failed)? {
+            let mut name = entry.file_name().to_string_lossy().into_owned();
+            if entry
+                .file_type()
+                .await
+                .is_ok_and(|file_type| file_type.is_dir())
+            {
+                name.push('/');
+            }
+            names.push(name);
+        }
+        names.sort();
+        Ok(truncate(names.join("\n")))
+    }
+}
+
+/// Creates or replaces a file of the project, the user approves the path.
+///
+/// Without a path there is nothing to approve, the call fails on its arguments.
+pub struct WriteFile {
+    root: PathBuf,
+}
+
+impl WriteFile {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl Tool for WriteFile {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "write_file".to_string(),
+            description: "Writes the whole content of a file, creating it and its directories \
+                          if needed, the path is relative to the project root"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string"},
+                    "content": {"type": "string"}
+                },
+                "required": ["path", "content"]
+            }),
+        }
+    }
+
+    fn permission_subject(&self, arguments: &serde_json::Value) -> Option<String> {
+        arguments["path"].as_str().map(str::to_string)
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<String, ToolError> {
+        let path = required(&arguments, "path")?;
+        let content = required(&arguments, "content")?;
+        let target = resolve(&self.root, path)?;
+        let failed = |err: std::io::Error| ToolError::Failed(format!("{}: {}", path, err));
+        if let Some(dir) = target.parent() {
+            tokio::fs::create_dir_all(dir).await.map_err(failed)?;
+        }
+        tokio::fs::write(&target, content).await.map_err(failed)?;
+        Ok(format!("Wrote {} bytes to {}", content.len(), path))
+    }
+}
+
+/// Runs a command with `sh -c` in the project root, the user approves the command line.
+///
+/// The output lines, of both stdout and stderr, are reported as they are printed. The
+/// command is killed if the turn is cancelled.
+pub struct Shell {
+    root: PathBuf,
+}
+
+impl Shell {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl Tool for Shell {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "shell".to_string(),
+            description: "Runs a shell command in the project root and returns its output"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {"command": {"type": "string"}},
+                "required": ["command"]
+            }),
+        }
+    }
+
+    fn permission_subject(&self, arguments: &serde_json::Value) -> Option<String> {
+        arguments["command"].as_str().map(str::to_string)
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<String, ToolError> {
+        let (progress, _) = tokio::sync::mpsc::unbounded_channel();
+        self.call_with_progress(arguments, progress).await
+    }
+
+    async fn call_with_progress(
+        &self,
+        arguments: serde_json::Value,
+        progress: ToolProgress,
+    ) -> Result<String, ToolError> {
+        let command = required(&arguments, "command")?;
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(&self.root)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| ToolError::Failed(err.to_string()))?;
+        let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+            return Err(ToolError::Failed("the output is not captured".to_string()));
+        };
+        let mut stdout = BufReader::new(stdout).lines();
+        let mut stderr = BufReader::new(stderr).lines();
+        let (mut stdout_open, mut stderr_open) = (true, true);
+        let mut output = String::new();
+        while stdout_open || stderr_open {
+            let line = tokio::select! {
+                line = stdout.next_line(), if stdout_open => line.ok().flatten().or_else(|| {
+                    stdout_open = false;
+                    None
+                }),
+                line = stderr.next_line(), if stderr_open => line.ok().flatten().or_else(|| {
+                    stderr_open = false;
+                    None
+                }),
+            };
+            if let Some(mut line) = line {
+                line.push('\n');
+                let _ = progress.send(line.clone());
+                output.push_str(&line);
+            }
+        }
+        let status = child
+            .wait()
+            .await
+            .map_err(|err| ToolError::Failed(err.to_string()))?;
+        let output = truncate(output);
+        if status.success() {
+            Ok(output)
+        } else {
+            Err(ToolError::Failed(format!("{}\n{}", status, output)))
+        }
+    }
+}
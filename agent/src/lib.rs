@@ -0,0 +1,5 @@
+pub mod builtin;
+pub mod model;
+pub mod provider;
+pub mod react;
+pub mod tool;
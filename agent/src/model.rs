@@ -0,0 +1,223 @@
+use crate::react::AgentError;
+use async_trait::async_trait;
+use futures::StreamExt as _;
+use futures::stream::BoxStream;
+
+/// A tool invocation requested by the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    /// Provider-assigned identifier used to match the observation with the call.
+    pub id: String,
+    /// The name of the tool to run.
+    pub name: String,
+    /// The JSON arguments generated by the model.
+    pub arguments: serde_json::Value,
+}
+
+/// The description of a tool as it is advertised to the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    /// JSON schema of the tool arguments.
+    pub parameters: serde_json::Value,
+}
+
+/// One entry of the conversation history kept by the agent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatMessage {
+    /// the user prompt
+    User(String),
+    /// the model reply, with the text and the actions it asked for
+    Assistant {
+        text: Option<String>,
+        tool_calls: Vec<ToolCall>,
+    },
+    /// the observation produced by running a tool call
+    ToolResult { call_id: String, content: String },
+}
+
+/// Everything the model needs to produce the next step.
+#[derive(Debug, Clone, Copy)]
+pub struct ModelRequest<'a> {
+    pub preamble: Option<&'a str>,
+    pub history: &'a [ChatMessage],
+    pub tools: &'a [ToolSpec],
+}
+
+/// The tokens counted by the provider for one model call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub input_tokens: u64,
+    /// the part of the input tokens read from the cache of the provider
+    pub cached_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// The price of a million tokens, in US dollars.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prices {
+    pub input: f64,
+    /// the price of the input tokens read from the cache
+    pub cached_input: f64,
+    pub output: f64,
+}
+
+impl Prices {
+    /// Returns the cost of a model call, in US dollars.
+    ///
+    /// The cached tokens are billed at the `cached_input` price, the rest of the input
+    /// tokens at the `input` one.
+    pub fn cost(&self, usage: Usage) -> f64 {
+        let uncached = usage.input_tokens.saturating_sub(usage.cached_tokens);
+        (uncached as f64 * self.input
+            + usage.cached_tokens as f64 * self.cached_input
+            + usage.output_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+/// The context windows and the prices of the models known by `ModelInfo::known`.
+const KNOWN_MODELS: &[(&str, u64, Prices)] = &[
+    (
+        "gpt-4o",
+        128_000,
+        Prices {
+            input: 2.5,
+            cached_input: 1.25,
+            output: 10.0,
+        },
+    ),
+    (
+        "gpt-4o-mini",
+        128_000,
+        Prices {
+            input: 0.15,
+            cached_input: 0.075,
+            output: 0.6,
+        },
+    ),
+    (
+        "claude-3-5-sonnet-latest",
+        200_000,
+        Prices {
+            input: 3.0,
+            cached_input: 0.3,
+            output: 15.0,
+        },
+    ),
+    (
+        "gemini-1.5-pro",
+        2_000_000,
+        Prices {
+            input: 1.25,
+            cached_input: 0.3125,
+            output: 5.0,
+        },
+    ),
+];
+
+/// What is known about a model, for the status of the session and its cost.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelInfo {
+    pub name: String,
+    /// the maximum number of tokens of a request with its response
+    pub context_window: Option<u64>,
+    pub prices: Option<Prices>,
+}
+
+impl ModelInfo {
+    /// Returns the information about a model by its name, only the name is known for the
+    /// models missing from `KNOWN_MODELS`.
+    pub fn known(name: &str) -> Self {
+        let known = KNOWN_MODELS.iter().find(|(known, _, _)| *known == name);
+        Self {
+            name: name.to_string(),
+            context_window: known.map(|(_, context_window, _)| *context_window),
+            prices: known.map(|(_, _, prices)| *prices),
+        }
+    }
+}
+
+/// The result of one model call.
+///
+/// A response without tool calls is a final answer, otherwise `text` and `thought`
+/// explain why the actions were chosen.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelResponse {
+    /// Reasoning content, if the provider exposes it separately from the text.
+    pub thought: Option<String>,
+    pub text: Option<String>,
+    pub tool_calls: Vec<ToolCall>,
+    /// The tokens of the call, if the provider reports them.
+    pub usage: Option<Usage>,
+}
+
+impl ModelResponse {
+    /// Splits the response into the events a streaming model would have produced.
+    pub fn into_events(self) -> Vec<ModelEvent> {
+        let mut events = Vec::with_capacity(self.tool_calls.len() + 3);
+        events.extend(self.thought.map(ModelEvent::ThoughtDelta));
+        events.extend(self.text.map(ModelEvent::TextDelta));
+        events.extend(self.tool_calls.into_iter().map(ModelEvent::ToolCall));
+        events.extend(self.usage.map(ModelEvent::Usage));
+        events
+    }
+
+    /// Adds a streamed event to the response being assembled.
+    pub fn push_event(&mut self, event: ModelEvent) {
+        match event {
+            ModelEvent::TextDelta(delta) => {
+                self.text.get_or_insert_with(String::new).push_str(&delta)
+            }
+            ModelEvent::ThoughtDelta(delta) => self
+                .thought
+                .get_or_insert_with(String::new)
+                .push_str(&delta),
+            ModelEvent::ToolCall(call) => self.tool_calls.push(call),
+            ModelEvent::Usage(usage) => self.usage = Some(usage),
+        }
+    }
+}
+
+/// A piece of a streamed model response.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelEvent {
+    /// a fragment of the response text
+    TextDelta(String),
+    /// a fragment of the reasoning content
+    ThoughtDelta(String),
+    /// a complete tool call
+    ToolCall(ToolCall),
+    /// the tokens of the call, at the end of the stream
+    Usage(Usage),
+}
+
+pub type ModelStream = BoxStream<'static, Result<ModelEvent, AgentError>>;
+
+/// A trait for the language model driving the agent.
+///
+/// It hides the provider behind a single completion call, so the ReAct loop can be
+/// driven by any backend, including scripted models in tests.
+#[async_trait]
+pub trait LanguageModel: Send + Sync {
+    /// Sends the conversation to the model and returns its next step.
+    async fn complete(&self, request: ModelRequest<'_>) -> Result<ModelResponse, AgentError>;
+
+    /// Sends the conversation to the model and returns its next step as a stream of events.
+    ///
+    /// The default implementation waits for `complete` and replays its result, providers
+    /// supporting streaming should override it to deliver tokens as they are generated.
+    async fn stream(&self, request: ModelRequest<'_>) -> Result<ModelStream, AgentError> {
+        let events = self.complete(request).await?.into_events();
+        Ok(futures::stream::iter(events.into_iter().map(Ok)).boxed())
+    }
+
+    /// Returns the name, the context window and the prices of the model.
+    ///
+    /// Nothing is known by default, the status then leaves out the context usage and the
+    /// cost.
+    fn info(&self) -> ModelInfo {
+        ModelInfo::default()
+    }
+}
@@ -0,0 +1,230 @@
+use crate::model::{
+    ChatMessage, LanguageModel, ModelEvent, ModelInfo, ModelRequest, ModelResponse, ModelStream,
+    ToolCall, Usage,
+};
+use crate::react::AgentError;
+use async_trait::async_trait;
+use futures::StreamExt as _;
+use rig::OneOrMany;
+use rig::client::{CompletionClient as _, ProviderClient as _};
+use rig::completion::{
+    AssistantContent, CompletionModel, CompletionRequest, CompletionRequestBuilder,
+    GetTokenUsage as _, Message, ToolDefinition,
+};
+use rig::message::{ToolResultContent, UserContent};
+use rig::providers::{anthropic, gemini, openai};
+use rig::streaming::StreamedAssistantContent;
+use std::sync::Arc;
+
+/// The providers accepted by `connect` with the variable holding their API key.
+pub const PROVIDERS: &[(&str, &str)] = &[
+    ("openai", "OPENAI_API_KEY"),
+    ("anthropic", "ANTHROPIC_API_KEY"),
+    ("gemini", "GEMINI_API_KEY"),
+];
+
+/// Creates the model `model` of the named provider.
+///
+/// The API key is read from the environment, see `PROVIDERS` for the variable names. The
+/// context window and the prices of the model come from `ModelInfo::known`.
+pub fn connect(provider: &str, model: &str) -> Result<Arc<dyn LanguageModel>, AgentError> {
+    let (_, key_var) = PROVIDERS
+        .iter()
+        .find(|(name, _)| *name == provider)
+        .ok_or_else(|| AgentError::Model(format!("Unknown provider: {}", provider)))?;
+    if std::env::var_os(key_var).is_none() {
+        return Err(AgentError::Model(format!("{} is not set", key_var)));
+    }
+    let info = ModelInfo::known(model);
+    Ok(match provider {
+        "anthropic" => Arc::new(
+            RigModel::new(anthropic::Client::from_env().completion_model(model)).with_info(info),
+        ),
+        "gemini" => Arc::new(
+            RigModel::new(gemini::Client::from_env().completion_model(model)).with_info(info),
+        ),
+        _ => Arc::new(
+            RigModel::new(openai::Client::from_env().completion_model(model)).with_info(info),
+        ),
+    })
+}
+
+/// A `LanguageModel` backed by any rig completion model.
+pub struct RigModel<M> {
+    model: M,
+    info: ModelInfo,
+}
+
+impl<M: CompletionModel> RigModel<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            info: ModelInfo::default(),
+        }
+    }
+
+    /// Sets what is known about the model, see `LanguageModel::info`.
+    pub fn with_info(mut self, info: ModelInfo) -> Self {
+        self.info = info;
+        self
+    }
+
+    fn build_request(&self, request: ModelRequest<'_>) -> Result<CompletionRequest, AgentError> {
+        let mut messages = to_rig_messages(request.history);
+        let prompt = messages
+            .pop()
+            .ok_or_else(|| AgentError::Model("empty conversation".to_string()))?;
+        let tools = request
+            .tools
+            .iter()
+            .map(|tool| ToolDefinition {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                parameters: tool.parameters.clone(),
+            })
+            .collect();
+
+        let mut builder = CompletionRequestBuilder::new(self.model.clone(), prompt)
+            .messages(messages)
+            .tools(tools);
+        if let Some(preamble) = request.preamble {
+            builder = builder.preamble(preamble.to_string());
+        }
+        Ok(builder.build())
+    }
+}
+
+#[async_trait]
+impl<M> LanguageModel for RigModel<M>
+where
+    M: CompletionModel + 'static,
+{
+    async fn complete(&self, request: ModelRequest<'_>) -> Result<ModelResponse, AgentError> {
+        let response = self
+            .model
+            .completion(self.build_request(request)?)
+            .await
+            .map_err(|err| AgentError::Model(err.to_string()))?;
+
+        let mut result = ModelResponse {
+            usage: Some(from_rig_usage(response.usage)),
+            ..ModelResponse::default()
+        };
+        for content in response.choice.into_iter() {
+            match content {
+                AssistantContent::Text(text) => append(&mut result.text, &text.text),
+                AssistantContent::Reasoning(reasoning) => {
+                    append(&mut result.thought, &reasoning.reasoning.join("\n"))
+                }
+                AssistantContent::ToolCall(call) => result.tool_calls.push(ToolCall {
+                    id: call.id,
+                    name: call.function.name,
+                    arguments: call.function.arguments,
+                }),
+            }
+        }
+        Ok(result)
+    }
+
+    async fn stream(&self, request: ModelRequest<'_>) -> Result<ModelStream, AgentError> {
+        let stream = self
+            .model
+            .stream(self.build_request(request)?)
+            .await
+            .map_err(|err| AgentError::Model(err.to_string()))?;
+
+        Ok(stream
+            .filter_map(|content| async move {
+                match content {
+                    Ok(StreamedAssistantContent::Text(text)) => {
+                        Some(Ok(ModelEvent::TextDelta(text.text)))
+                    }
+                    Ok(StreamedAssistantContent::Reasoning(reasoning)) => {
+                        Some(Ok(ModelEvent::ThoughtDelta(reasoning.reasoning.join("\n"))))
+                    }
+                    Ok(StreamedAssistantContent::ToolCall(call)) => {
+                        Some(Ok(ModelEvent::ToolCall(ToolCall {
+                            id: call.id,
+                            name: call.function.name,
+                            arguments: call.function.arguments,
+                        })))
+                    }
+                    Ok(StreamedAssistantContent::Final(response)) => response
+                        .token_usage()
+                        .map(|usage| Ok(ModelEvent::Usage(from_rig_usage(usage)))),
+                    Err(err) => Some(Err(AgentError::Model(err.to_string()))),
+                }
+            })
+            .boxed())
+    }
+
+    fn info(&self) -> ModelInfo {
+        self.info.clone()
+    }
+}
+
+fn from_rig_usage(usage: rig::completion::Usage) -> Usage {
+    // the cached input tokens are not reported through rig
+    Usage {
+        input_tokens: usage.input_tokens,
+        cached_tokens: 0,
+        output_tokens: usage.output_tokens,
+    }
+}
+
+fn append(target: &mut Option<String>, text: &str) {
+    match target {
+        Some(existing) => existing.push_str(text),
+        None => *target = Some(text.to_string()),
+    }
+}
+
+/// Converts the agent history into rig messages.
+///
+/// Consecutive tool results are merged into one user message, as providers expect all
+/// observations of a step to be answered together.
+fn to_rig_messages(history: &[ChatMessage]) -> Vec<Message> {
+    let mut messages = Vec::with_capacity(history.len());
+    let mut results: Vec<UserContent> = Vec::new();
+
+    let flush = |results: &mut Vec<UserContent>, messages: &mut Vec<Message>| {
+        if let Ok(content) = OneOrMany::many(std::mem::take(results)) {
+            messages.push(Message::User { content });
+        }
+    };
+
+    for entry in history {
+        match entry {
+            ChatMessage::ToolResult { call_id, content } => {
+                results.push(UserContent::tool_result(
+                    call_id.clone(),
+                    OneOrMany::one(ToolResultContent::text(content.clone())),
+                ));
+            }
+            ChatMessage::User(text) => {
+                flush(&mut results, &mut messages);
+                messages.push(Message::user(text.clone()));
+            }
+            ChatMessage::Assistant { text, tool_calls } => {
+                flush(&mut results, &mut messages);
+                let mut content = Vec::new();
+                if let Some(text) = text.as_ref().filter(|t| !t.is_empty()) {
+                    content.push(AssistantContent::text(text.clone()));
+                }
+                for call in tool_calls {
+                    content.push(AssistantContent::tool_call(
+                        call.id.clone(),
+                        call.name.clone(),
+                        call.arguments.clone(),
+                    ));
+                }
+                // an assistant message without text nor tool calls has nothing to send
+                if let Ok(content) = OneOrMany::many(content) {
+                    messages.push(Message::Assistant { id: None, content });
+                }
+            }
+        }
+    }
+    flush(&mut results, &mut messages);
+    messages
+}
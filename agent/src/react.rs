@@ -0,0 +1,490 @@
+use crate::model::{ChatMessage, LanguageModel, ModelEvent, ModelRequest, ModelResponse, Usage};
+use crate::tool::{Tool, ToolError, Toolbox};
+use console::common::{
+    Console, ConsoleError, Message, Permission, PermissionRequest, SessionStatistics,
+    ToolCallStatistics, ToolStatus, TurnStatistics,
+};
+use console::permission::Permissions;
+use futures::StreamExt as _;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// The console is refreshed at least this often while a tool runs, to animate spinners.
+const TOOL_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The default number of model calls allowed for a single turn.
+pub const DEFAULT_MAX_STEPS: usize = 25;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AgentError {
+    /// The model provider failed or returned an unusable response
+    #[error("Model error: {0}")]
+    Model(String),
+    /// The console failed while the agent was talking to the user
+    #[error(transparent)]
+    Console(#[from] ConsoleError),
+    /// A tool call needs an approval but the console has no input to ask for it
+    #[error("{tool} needs an approval for: {subject}")]
+    ApprovalNeeded { tool: String, subject: String },
+}
+
+/// Why a turn has ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The model answered without asking for more actions
+    FinalAnswer,
+    /// The turn was stopped after `max_steps` model calls
+    StepLimit,
+    /// The user cancelled the turn from the console
+    Cancelled,
+}
+
+/// The outcome of `Agent::run_turn`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnReport {
+    pub stop: StopReason,
+    /// The final answer, if the model produced one.
+    pub answer: Option<String>,
+    /// The number of model calls made during the turn.
+    pub steps: usize,
+    /// The tokens, the tool calls, the timings and the cost of the turn.
+    pub statistics: TurnStatistics,
+}
+
+/// A ReAct agent running the Thought -> Action -> Observation loop.
+///
+/// The agent keeps the conversation history between turns and talks to the user only
+/// through a `Console`: the model text is streamed to the response area, reasoning,
+/// actions and observations go to the thinking display.
+///
+/// Tools needing an approval only run once the user allows them, or a rule of the
+/// permissions does.
+///
+/// The model, the context usage and the cost of the session are shown in the console
+/// status, as far as the model reports its tokens. The statistics of every turn are sent
+/// to the console when the turn ends and added to the session totals.
+pub struct Agent {
+    model: Arc<dyn LanguageModel>,
+    toolbox: Toolbox,
+    preamble: Option<String>,
+    max_steps: usize,
+    history: Vec<ChatMessage>,
+    permissions: Permissions,
+    /// the tokens of the last model call, the size of the conversation
+    context_tokens: u64,
+    /// the totals of the finished turns
+    session: SessionStatistics,
+}
+
+impl Agent {
+    /// Creates an agent without tools, using `DEFAULT_MAX_STEPS`.
+    pub fn new(model: Arc<dyn LanguageModel>) -> Self {
+        Self {
+            model,
+            toolbox: Toolbox::default(),
+            preamble: None,
+            max_steps: DEFAULT_MAX_STEPS,
+            history: Vec::new(),
+            permissions: Permissions::in_memory(),
+            context_tokens: 0,
+            session: SessionStatistics::default(),
+        }
+    }
+
+    /// Sets the system prompt sent with every model call.
+    pub fn with_preamble(mut self, preamble: impl Into<String>) -> Self {
+        self.preamble = Some(preamble.into());
+        self
+    }
+
+    /// Makes a tool available to the model.
+    pub fn with_tool(mut self, tool: impl Tool + 'static) -> Self {
+        self.toolbox.add(Arc::new(tool));
+        self
+    }
+
+    /// Makes several tools available to the model, e.g. `builtin::project_tools`.
+    pub fn with_tools(mut self, tools: impl IntoIterator<Item = Arc<dyn Tool>>) -> Self {
+        for tool in tools {
+            self.toolbox.add(tool);
+        }
+        self
+    }
+
+    /// Sets the maximum number of model calls per turn, at least one call is always made.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    /// Switches to another model, the conversation goes on with it.
+    pub fn set_model(&mut self, model: Arc<dyn LanguageModel>) {
+        self.model = model;
+    }
+
+    /// Sets the approval rules, e.g. the permissions file of the project.
+    pub fn with_permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    pub fn permissions(&self) -> &Permissions {
+        &self.permissions
+    }
+
+    pub fn history(&self) -> &[ChatMessage] {
+        &self.history
+    }
+
+    /// Returns the totals of the turns of the session, the conversation may have been
+    /// cleared meanwhile.
+    pub fn session_statistics(&self) -> &SessionStatistics {
+        &self.session
+    }
+
+    /// Forgets the conversation, the next turn starts from scratch.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.context_tokens = 0;
+    }
+
+    /// Shows the model, the share of its context window taken by the conversation and the
+    /// cost of the session in the console status.
+    ///
+    /// The context usage and the cost are left out when the model doesn't tell its context
+    /// window or its prices.
+    pub fn update_status<C>(&self, console: &mut C)
+    where
+        C: Console + Send + ?Sized,
+    {
+        self.show_status(console, self.session.cost);
+    }
+
+    /// Shows the status with `cost` as the cost of the session.
+    fn show_status<C>(&self, console: &mut C, cost: Option<f64>)
+    where
+        C: Console + Send + ?Sized,
+    {
+        let info = self.model.info();
+        let status = console.status();
+        status.model = Some(info.name).filter(|name| !name.is_empty());
+        status.context_usage = info
+            .context_window
+            .filter(|context_window| *context_window > 0)
+            .map(|context_window| (self.context_tokens * 100 / context_window).min(100) as u8);
+        status.cost = cost.or(info.prices.map(|_| 0.0));
+    }
+
+    /// Accounts for the tokens of a model call in the statistics of the turn.
+    fn add_usage(&mut self, usage: Usage, statistics: &mut TurnStatistics) {
+        self.context_tokens = usage.input_tokens + usage.output_tokens;
+        statistics.input_tokens += usage.input_tokens;
+        statistics.cached_tokens += usage.cached_tokens;
+        statistics.output_tokens += usage.output_tokens;
+        if let Some(prices) = self.model.info().prices {
+            *statistics.cost.get_or_insert(0.0) += prices.cost(usage);
+        }
+    }
+
+    /// Runs one user turn.
+    ///
+    /// The model is called until it gives a final answer or the step limit is hit. Every
+    /// requested tool is executed and its output, or error, is sent back as the
+    /// observation. The console must be in the responding state.
+    ///
+    /// When the console cancellation token fires, the in-flight model request or tool call
+    /// is dropped, the history is restored to its state before the turn and the turn ends
+    /// with `StopReason::Cancelled`. The history is restored the same way when the turn
+    /// fails.
+    ///
+    /// Unless the turn fails, its statistics are sent to the console with
+    /// `Console::add_statistics` before returning.
+    pub async fn run_turn<C>(
+        &mut self,
+        console: &mut C,
+        prompt: String,
+    ) -> Result<TurnReport, AgentError>
+    where
+        C: Console + Send + ?Sized,
+    {
+        self.update_status(console);
+        let cancellation = console.cancellation_token().unwrap_or_default();
+        let history_len = self.history.len();
+        let started = Instant::now();
+        let mut statistics = TurnStatistics::default();
+
+        let result = tokio::select! {
+            biased;
+            _ = cancellation.cancelled() => Err(AgentError::Console(ConsoleError::Cancelled)),
+            result = self.run_steps(console, prompt, started, &mut statistics) => result,
+        };
+
+        let mut report = match result {
+            Ok(report) => report,
+            Err(err) => {
+                // a failed turn leaves no trace, so the next prompt doesn't follow a
+                // dangling one
+                self.history.truncate(history_len);
+                if !matches!(err, AgentError::Console(ConsoleError::Cancelled)) {
+                    return Err(err);
+                }
+                console
+                    .add_message(Message::Warning("Cancelled.".to_string()))
+                    .await?;
+                TurnReport {
+                    stop: StopReason::Cancelled,
+                    answer: None,
+                    steps: statistics.model_calls,
+                    statistics: TurnStatistics::default(),
+                }
+            }
+        };
+        statistics.duration = started.elapsed();
+        self.session.add_turn(&statistics);
+        self.update_status(console);
+        console.add_statistics(statistics.clone()).await?;
+        report.statistics = statistics;
+        Ok(report)
+    }
+
+    /// Asks the user to approve a tool call unless no approval is needed or a rule gives it.
+    ///
+    /// Returns the observation sent to the model when the user denies the call. Fails with
+    /// `AgentError::ApprovalNeeded` when the console has no input to ask with.
+    async fn authorize<C>(
+        &mut self,
+        console: &mut C,
+        name: &str,
+        tool: &Option<Arc<dyn Tool>>,
+        arguments: &serde_json::Value,
+    ) -> Result<Option<String>, AgentError>
+    where
+        C: Console + Send + ?Sized,
+    {
+        let Some(subject) = tool
+            .as_ref()
+            .and_then(|tool| tool.permission_subject(arguments))
+        else {
+            return Ok(None);
+        };
+        if self.permissions.is_allowed(name, &subject) {
+            return Ok(None);
+        }
+        let request = PermissionRequest::new(name.to_string(), subject);
+        let permission = match console.request_permission(request.clone()).await {
+            Err(ConsoleError::NoInput) => {
+                return Err(AgentError::ApprovalNeeded {
+                    tool: request.tool,
+                    subject: request.subject,
+                });
+            }
+            permission => permission?,
+        };
+        match permission {
+            Permission::AllowOnce => {}
+            Permission::AllowSession => self.permissions.allow_session(request.rule()),
+            Permission::AllowAlways => {
+                if let Err(err) = self.permissions.allow_always(request.rule()) {
+                    console
+                        .add_message(Message::Warning(format!(
+                            "Failed to save the permission: {}",
+                            err
+                        )))
+                        .await?;
+                }
+            }
+            Permission::Deny(feedback) if feedback.is_empty() => {
+                return Ok(Some("Error: the user denied this action.".to_string()));
+            }
+            Permission::Deny(feedback) => {
+                return Ok(Some(format!(
+                    "Error: the user denied this action with the feedback: {}",
+                    feedback
+                )));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Runs the steps of a turn started at `started`, accounting for them in `statistics`.
+    ///
+    /// The statistics of the returned report are left empty, `run_turn` fills them in.
+    async fn run_steps<C>(
+        &mut self,
+        console: &mut C,
+        prompt: String,
+        started: Instant,
+        statistics: &mut TurnStatistics,
+    ) -> Result<TurnReport, AgentError>
+    where
+        C: Console + Send + ?Sized,
+    {
+        self.history.push(ChatMessage::User(prompt));
+        let tools = self.toolbox.specs();
+
+        for step in 1..=self.max_steps {
+            statistics.model_calls = step;
+            let mut stream = self
+                .model
+                .stream(ModelRequest {
+                    preamble: self.preamble.as_deref(),
+                    history: &self.history,
+                    tools: &tools,
+                })
+                .await?;
+
+            let mut response = ModelResponse::default();
+            while let Some(event) = stream.next().await {
+                let event = event?;
+                if matches!(
+                    event,
+                    ModelEvent::TextDelta(_) | ModelEvent::ThoughtDelta(_)
+                ) {
+                    statistics
+                        .time_to_first_token
+                        .get_or_insert_with(|| started.elapsed());
+                }
+                match &event {
+                    ModelEvent::TextDelta(delta) => {
+                        console.add_response_delta(delta.clone()).await?
+                    }
+                    ModelEvent::ThoughtDelta(delta) => {
+                        console.add_thinking_delta(delta.clone()).await?
+                    }
+                    ModelEvent::ToolCall(_) | ModelEvent::Usage(_) => {}
+                }
+                response.push_event(event);
+            }
+            if let Some(usage) = response.usage {
+                self.add_usage(usage, statistics);
+                let cost = match (self.session.cost, statistics.cost) {
+                    (Some(session), Some(turn)) => Some(session + turn),
+                    (session, turn) => session.or(turn),
+                };
+                self.show_status(console, cost);
+            }
+
+            if response.tool_calls.is_empty() {
+                let answer = response.text.unwrap_or_default();
+                // an empty assistant message is rejected by the providers on the next turn
+                if !answer.is_empty() {
+                    self.history.push(ChatMessage::Assistant {
+                        text: Some(answer.clone()),
+                        tool_calls: Vec::new(),
+                    });
+                }
+                return Ok(TurnReport {
+                    stop: StopReason::FinalAnswer,
+                    answer: Some(answer),
+                    steps: step,
+                    statistics: TurnStatistics::default(),
+                });
+            }
+
+            self.history.push(ChatMessage::Assistant {
+                text: response.text,
+                tool_calls: response.tool_calls.clone(),
+            });
+
+            for call in response.tool_calls {
+                let tool = self.toolbox.get(&call.name);
+                if let Some(denial) = self
+                    .authorize(console, &call.name, &tool, &call.arguments)
+                    .await?
+                {
+                    self.history.push(ChatMessage::ToolResult {
+                        call_id: call.id,
+                        content: denial,
+                    });
+                    continue;
+                }
+                console
+                    .start_tool_call(call.name.clone(), call.arguments.to_string())
+                    .await?;
+                let started = Instant::now();
+                let (observation, status) = match tool {
+                    Some(tool) => match call_tool(console, tool, call.arguments).await? {
+                        Ok(output) => (output, ToolStatus::Succeeded),
+                        Err(err) => (
+                            format!("Error: {}", err),
+                            ToolStatus::Failed(err.to_string()),
+                        ),
+                    },
+                    None => {
+                        let error = format!("unknown tool `{}`", call.name);
+                        (format!("Error: {}", error), ToolStatus::Failed(error))
+                    }
+                };
+                let duration = started.elapsed();
+                statistics.tool_calls.push(ToolCallStatistics {
+                    name: call.name.clone(),
+                    duration,
+                    success: status == ToolStatus::Succeeded,
+                });
+                console.finish_tool_call(status, duration).await?;
+                console
+                    .add_thinking_text(format!("Observation: {}", observation))
+                    .await?;
+                self.history.push(ChatMessage::ToolResult {
+                    call_id: call.id,
+                    content: observation,
+                });
+            }
+        }
+
+        console
+            .add_message(Message::Warning(format!(
+                "Stopped after reaching the limit of {} steps.",
+                self.max_steps
+            )))
+            .await?;
+        Ok(TurnReport {
+            stop: StopReason::StepLimit,
+            answer: None,
+            steps: self.max_steps,
+            statistics: TurnStatistics::default(),
+        })
+    }
+}
+
+/// Runs a tool, forwarding its progress to the console as it arrives.
+///
+/// The console is also refreshed regularly while the tool is silent. A tool that reports
+/// no progress has its result shown as its output instead.
+async fn call_tool<C>(
+    console: &mut C,
+    tool: Arc<dyn Tool>,
+    arguments: serde_json::Value,
+) -> Result<Result<String, ToolError>, AgentError>
+where
+    C: Console + Send + ?Sized,
+{
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let call = tool.call_with_progress(arguments, sender);
+    tokio::pin!(call);
+    let mut ticks = tokio::time::interval(TOOL_REFRESH_INTERVAL);
+    let mut reported = false;
+    let result = loop {
+        tokio::select! {
+            biased;
+            Some(output) = receiver.recv() => {
+                reported = true;
+                console.add_tool_output(output).await?;
+            }
+            result = &mut call => break result,
+            _ = ticks.tick() => console.add_tool_output(String::new()).await?,
+        }
+    };
+    while let Ok(output) = receiver.try_recv() {
+        reported = true;
+        console.add_tool_output(output).await?;
+    }
+    if !reported {
+        match &result {
+            Ok(output) => console.add_tool_output(output.clone()).await?,
+            Err(err) => console.add_tool_output(err.to_string()).await?,
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,77 @@
+use crate::model::ToolSpec;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ToolError {
+    /// The arguments generated by the model do not match the tool schema
+    #[error("Invalid arguments: {0}")]
+    InvalidArguments(String),
+    /// The tool was started but failed
+    #[error("Tool failed: {0}")]
+    Failed(String),
+}
+
+/// Receives the progress of a running tool, e.g. the output of a command as it is printed.
+pub type ToolProgress = UnboundedSender<String>;
+
+/// A trait for the actions the agent can take.
+///
+/// Errors returned by `call` are not fatal for the turn, they are sent back to the
+/// model as the observation so it can correct itself.
+#[async_trait]
+pub trait Tool: Send + Sync {
+    /// Returns the name, description and argument schema advertised to the model.
+    fn spec(&self) -> ToolSpec;
+
+    /// Runs the tool with the arguments generated by the model and returns the observation.
+    async fn call(&self, arguments: serde_json::Value) -> Result<String, ToolError>;
+
+    /// Returns what the user approves before the tool runs with these arguments, e.g. the
+    /// command line of a shell tool, or `None` if the tool runs without approval.
+    ///
+    /// The default implementation needs no approval, which suits read-only tools.
+    fn permission_subject(&self, _arguments: &serde_json::Value) -> Option<String> {
+        None
+    }
+
+    /// Runs the tool like `call` and reports its progress while it runs.
+    ///
+    /// Long-running tools override this so the user sees them working, the default
+    /// implementation reports nothing.
+    async fn call_with_progress(
+        &self,
+        arguments: serde_json::Value,
+        _progress: ToolProgress,
+    ) -> Result<String, ToolError> {
+        self.call(arguments).await
+    }
+}
+
+/// The set of tools available to the agent, indexed by name.
+#[derive(Default, Clone)]
+pub struct Toolbox {
+    tools: BTreeMap<String, Arc<dyn Tool>>,
+}
+
+impl Toolbox {
+    /// Adds a tool, replacing any previous tool with the same name.
+    pub fn add(&mut self, tool: Arc<dyn Tool>) {
+        self.tools.insert(tool.spec().name, tool);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.get(name).cloned()
+    }
+
+    /// Returns the specs of all tools in name order.
+    pub fn specs(&self) -> Vec<ToolSpec> {
+        self.tools.values().map(|tool| tool.spec()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+}
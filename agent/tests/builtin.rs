@@ -0,0 +1,101 @@
+use agent::builtin::{ListFiles, ReadFile, Shell, WriteFile, project_tools};
+use agent::tool::{Tool as _, ToolError};
+use serde_json::json;
+
+#[test]
+fn test_project_tools() {
+    let names: Vec<String> = project_tools(std::path::Path::new("."))
+        .iter()
+        .map(|tool| tool.spec().name)
+        .collect();
+    assert_eq!(names, ["read_file", "list_files", "write_file", "shell"]);
+}
+
+#[tokio::test]
+async fn test_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let write = WriteFile::new(dir.path());
+    let arguments = json!({"path": "src/lib.rs", "content": "pub fn f() {}\n"});
+
+    assert_eq!(
+        write.permission_subject(&arguments).as_deref(),
+        Some("src/lib.rs")
+    );
+    assert_eq!(
+        write.call(arguments).await.unwrap(),
+        "Wrote 14 bytes to src/lib.rs"
+    );
+    assert_eq!(
+        ReadFile::new(dir.path())
+            .call(json!({"path": "src/lib.rs"}))
+            .await
+            .unwrap(),
+        "pub fn f() {}\n"
+    );
+    std::fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+    let list = ListFiles::new(dir.path());
+    assert_eq!(list.call(json!({})).await.unwrap(), "Cargo.toml\nsrc/");
+    assert!(list.permission_subject(&json!({})).is_none());
+    assert!(matches!(
+        ReadFile::new(dir.path())
+            .call(json!({"path": "missing.rs"}))
+            .await,
+        Err(ToolError::Failed(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_paths_stay_in_the_project() {
+    let dir = tempfile::tempdir().unwrap();
+    let read = ReadFile::new(dir.path().join("project"));
+
+    for path in ["../secret", "/etc/passwd", "src/../../secret"] {
+        assert!(
+            matches!(
+                read.call(json!({ "path": path })).await,
+                Err(ToolError::InvalidArguments(_))
+            ),
+            "{}",
+            path
+        );
+    }
+    assert!(matches!(
+        WriteFile::new(dir.path())
+            .call(json!({"path": "a.rs"}))
+            .await,
+        Err(ToolError::InvalidArguments(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_shell() {
+    let dir = tempfile::tempdir().unwrap();
+    let shell = Shell::new(dir.path());
+    let arguments = json!({"command": "echo out; echo err >&2"});
+    let (progress, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+    assert_eq!(
+        shell.permission_subject(&arguments).as_deref(),
+        Some("echo out; echo err >&2")
+    );
+    let output = shell.call_with_progress(arguments, progress).await.unwrap();
+    let mut lines = Vec::new();
+    while let Ok(line) = receiver.try_recv() {
+        lines.push(line);
+    }
+    lines.sort();
+    assert_eq!(lines, ["err\n", "out\n"]);
+    assert_eq!(output.len(), 8);
+
+    assert_eq!(
+        shell.call(json!({"command": "pwd"})).await.unwrap().trim(),
+        dir.path().canonicalize().unwrap().display().to_string()
+    );
+    match shell.call(json!({"command": "echo failing; exit 3"})).await {
+        Err(ToolError::Failed(message)) => {
+            assert!(message.contains("exit status: 3"), "{}", message);
+            assert!(message.contains("failing"), "{}", message);
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+}
@@ -0,0 +1,611 @@
+use agent::model::{
+    ChatMessage, LanguageModel, ModelEvent, ModelInfo, ModelRequest, ModelResponse, ModelStream,
+    Prices, ToolCall, ToolSpec, Usage,
+};
+use agent::react::{Agent, AgentError, StopReason};
+use agent::tool::{Tool, ToolError, ToolProgress};
+use async_trait::async_trait;
+use console::common::{Console as _, Observability, Permission};
+use console::scripted::{Interaction, Record, ScriptedConsole};
+use console::stdio::StdIo;
+use futures::StreamExt as _;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// A model replaying a fixed list of responses and recording the requests it got.
+#[derive(Default)]
+struct ScriptedModel {
+    responses: Mutex<VecDeque<ModelResponse>>,
+    requests: Mutex<Vec<Vec<ChatMessage>>>,
+}
+
+impl ScriptedModel {
+    fn new(responses: Vec<ModelResponse>) -> Arc<Self> {
+        Arc::new(Self {
+            responses: Mutex::new(responses.into()),
+            requests: Mutex::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl LanguageModel for ScriptedModel {
+    async fn complete(&self, request: ModelRequest<'_>) -> Result<ModelResponse, AgentError> {
+        self.requests.lock().unwrap().push(request.history.to_vec());
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| AgentError::Model("no more responses".to_string()))
+    }
+}
+
+/// A model streaming its answer in small fragments.
+struct StreamingModel;
+
+#[async_trait]
+impl LanguageModel for StreamingModel {
+    async fn complete(&self, _request: ModelRequest<'_>) -> Result<ModelResponse, AgentError> {
+        Err(AgentError::Model("only streaming is supported".to_string()))
+    }
+
+    async fn stream(&self, _request: ModelRequest<'_>) -> Result<ModelStream, AgentError> {
+        let events = vec![
+            Ok(ModelEvent::ThoughtDelta("pondering".to_string())),
+            Ok(ModelEvent::TextDelta("Hello, ".to_string())),
+            Ok(ModelEvent::TextDelta("world".to_string())),
+        ];
+        Ok(futures::stream::iter(events).boxed())
+    }
+}
+
+/// A model that never answers, like a stalled provider.
+struct PendingModel;
+
+#[async_trait]
+impl LanguageModel for PendingModel {
+    async fn complete(&self, _request: ModelRequest<'_>) -> Result<ModelResponse, AgentError> {
+        futures::future::pending().await
+    }
+}
+
+struct Echo;
+
+#[async_trait]
+impl Tool for Echo {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "echo".to_string(),
+            description: "Returns its input".to_string(),
+            parameters: json!({"type": "object", "properties": {"text": {"type": "string"}}}),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<String, ToolError> {
+        arguments["text"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| ToolError::InvalidArguments("text is required".to_string()))
+    }
+}
+
+/// A tool reporting its progress before failing, like a failing test run.
+struct FailingTests;
+
+#[async_trait]
+impl Tool for FailingTests {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "run_tests".to_string(),
+            description: "Runs the tests".to_string(),
+            parameters: json!({"type": "object"}),
+        }
+    }
+
+    async fn call(&self, _arguments: serde_json::Value) -> Result<String, ToolError> {
+        Err(ToolError::Failed("1 test failed".to_string()))
+    }
+
+    async fn call_with_progress(
+        &self,
+        arguments: serde_json::Value,
+        progress: ToolProgress,
+    ) -> Result<String, ToolError> {
+        progress.send("running 2 tests\n".to_string()).unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        progress.send("test a ... FAILED\n".to_string()).unwrap();
+        self.call(arguments).await
+    }
+}
+
+/// A tool running commands, which need the approval of the user.
+struct Shell;
+
+#[async_trait]
+impl Tool for Shell {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "shell".to_string(),
+            description: "Runs a command".to_string(),
+            parameters: json!({"type": "object", "properties": {"command": {"type": "string"}}}),
+        }
+    }
+
+    fn permission_subject(&self, arguments: &serde_json::Value) -> Option<String> {
+        arguments["command"].as_str().map(str::to_string)
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<String, ToolError> {
+        Ok(format!("ran {}", arguments["command"]))
+    }
+}
+
+fn shell_call(id: &str, command: &str) -> ModelResponse {
+    ModelResponse {
+        tool_calls: vec![ToolCall {
+            id: id.to_string(),
+            name: "shell".to_string(),
+            arguments: json!({ "command": command }),
+        }],
+        ..ModelResponse::default()
+    }
+}
+
+fn answer(text: &str) -> ModelResponse {
+    ModelResponse {
+        text: Some(text.to_string()),
+        ..ModelResponse::default()
+    }
+}
+
+/// A model answering every prompt with the same token counts and known prices.
+struct MeteredModel;
+
+#[async_trait]
+impl LanguageModel for MeteredModel {
+    async fn complete(&self, _request: ModelRequest<'_>) -> Result<ModelResponse, AgentError> {
+        Ok(ModelResponse {
+            usage: Some(Usage {
+                input_tokens: 300,
+                cached_tokens: 200,
+                output_tokens: 100,
+            }),
+            ..answer("ok")
+        })
+    }
+
+    fn info(&self) -> ModelInfo {
+        ModelInfo {
+            name: "metered".to_string(),
+            context_window: Some(1000),
+            prices: Some(Prices {
+                input: 10.0,
+                cached_input: 1.0,
+                output: 20.0,
+            }),
+        }
+    }
+}
+
+fn echo_call(id: &str, arguments: serde_json::Value) -> ModelResponse {
+    ModelResponse {
+        text: Some("I should echo it".to_string()),
+        thought: None,
+        tool_calls: vec![ToolCall {
+            id: id.to_string(),
+            name: "echo".to_string(),
+            arguments,
+        }],
+        usage: None,
+    }
+}
+
+#[tokio::test]
+async fn test_final_answer() {
+    let model = ScriptedModel::new(vec![answer("42")]);
+    let mut agent = Agent::new(model.clone());
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&b""[..], &mut output);
+
+    console.start_responding().await.unwrap();
+    let report = agent
+        .run_turn(&mut console, "question".to_string())
+        .await
+        .unwrap();
+    console.stop_responding().await.unwrap();
+
+    assert_eq!(report.stop, StopReason::FinalAnswer);
+    assert_eq!(report.answer.as_deref(), Some("42"));
+    assert_eq!(report.steps, 1);
+    assert_eq!(agent.history().len(), 2);
+    assert!(String::from_utf8(output).unwrap().contains("42"));
+}
+
+#[tokio::test]
+async fn test_tool_observation_is_sent_back() {
+    let model = ScriptedModel::new(vec![
+        echo_call("call-1", json!({"text": "pong"})),
+        answer("done"),
+    ]);
+    let mut agent = Agent::new(model.clone()).with_tool(Echo);
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&b""[..], &mut output);
+    console.observability(Some(Observability {
+        statistics: false,
+        thinking: true,
+    }));
+
+    console.start_responding().await.unwrap();
+    let report = agent
+        .run_turn(&mut console, "ping".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(report.steps, 2);
+    let requests = model.requests.lock().unwrap();
+    assert_eq!(
+        requests[1].last(),
+        Some(&ChatMessage::ToolResult {
+            call_id: "call-1".to_string(),
+            content: "pong".to_string()
+        })
+    );
+    let output_str = String::from_utf8(output).unwrap();
+    assert!(output_str.contains("I should echo it"));
+    assert!(output_str.contains("Action: echo"));
+    assert!(output_str.contains("Observation: pong"));
+}
+
+#[tokio::test]
+async fn test_tool_errors_become_observations() {
+    let model = ScriptedModel::new(vec![
+        echo_call("call-1", json!({})),
+        ModelResponse {
+            tool_calls: vec![ToolCall {
+                id: "call-2".to_string(),
+                name: "missing".to_string(),
+                arguments: json!({}),
+            }],
+            ..ModelResponse::default()
+        },
+        answer("done"),
+    ]);
+    let mut agent = Agent::new(model.clone()).with_tool(Echo);
+    let mut console = StdIo::new_with_buffers(&b""[..], Vec::new());
+
+    console.start_responding().await.unwrap();
+    agent
+        .run_turn(&mut console, "ping".to_string())
+        .await
+        .unwrap();
+
+    let requests = model.requests.lock().unwrap();
+    assert!(matches!(
+        requests[1].last(),
+        Some(ChatMessage::ToolResult { content, .. }) if content.contains("text is required")
+    ));
+    assert!(matches!(
+        requests[2].last(),
+        Some(ChatMessage::ToolResult { content, .. }) if content.contains("unknown tool `missing`")
+    ));
+}
+
+#[tokio::test]
+async fn test_step_limit() {
+    let model = ScriptedModel::new(vec![
+        echo_call("call-1", json!({"text": "a"})),
+        echo_call("call-2", json!({"text": "b"})),
+        answer("never"),
+    ]);
+    let mut agent = Agent::new(model.clone()).with_tool(Echo).with_max_steps(2);
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&b""[..], &mut output);
+
+    console.start_responding().await.unwrap();
+    let report = agent
+        .run_turn(&mut console, "loop".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(report.stop, StopReason::StepLimit);
+    assert_eq!(report.answer, None);
+    assert_eq!(model.requests.lock().unwrap().len(), 2);
+    assert!(
+        String::from_utf8(output)
+            .unwrap()
+            .contains("limit of 2 steps")
+    );
+}
+
+#[tokio::test]
+async fn test_empty_answer_is_not_kept() {
+    let model = ScriptedModel::new(vec![answer(""), answer("42")]);
+    let mut agent = Agent::new(model.clone());
+    let mut console = StdIo::new_with_buffers(&b""[..], Vec::new());
+
+    for prompt in ["question", "again"] {
+        console.start_responding().await.unwrap();
+        agent
+            .run_turn(&mut console, prompt.to_string())
+            .await
+            .unwrap();
+        console.stop_responding().await.unwrap();
+    }
+
+    let requests = model.requests.lock().unwrap();
+    assert_eq!(
+        requests[1],
+        [
+            ChatMessage::User("question".to_string()),
+            ChatMessage::User("again".to_string())
+        ]
+    );
+    assert_eq!(agent.history().len(), 3);
+}
+
+#[tokio::test]
+async fn test_model_error_ends_turn() {
+    let model = ScriptedModel::new(vec![]);
+    let mut agent = Agent::new(model);
+    let mut console = StdIo::new_with_buffers(&b""[..], Vec::new());
+
+    console.start_responding().await.unwrap();
+    let result = agent.run_turn(&mut console, "hello".to_string()).await;
+    assert!(matches!(result, Err(AgentError::Model(_))));
+    assert!(agent.history().is_empty());
+}
+
+#[tokio::test]
+async fn test_failed_turn_is_rolled_back() {
+    let model = ScriptedModel::new(vec![echo_call("call-1", json!({"text": "pong"}))]);
+    let mut agent = Agent::new(model.clone()).with_tool(Echo);
+    let mut console = StdIo::new_with_buffers(&b""[..], Vec::new());
+
+    console.start_responding().await.unwrap();
+    let result = agent.run_turn(&mut console, "first".to_string()).await;
+    assert!(matches!(result, Err(AgentError::Model(_))));
+    model.responses.lock().unwrap().push_back(answer("done"));
+    agent
+        .run_turn(&mut console, "second".to_string())
+        .await
+        .unwrap();
+
+    let requests = model.requests.lock().unwrap();
+    assert_eq!(
+        requests.last().unwrap(),
+        &[ChatMessage::User("second".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn test_streamed_answer() {
+    let mut agent = Agent::new(Arc::new(StreamingModel));
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&b""[..], &mut output);
+    console.observability(Some(Observability {
+        statistics: false,
+        thinking: true,
+    }));
+
+    console.start_responding().await.unwrap();
+    let report = agent
+        .run_turn(&mut console, "greet".to_string())
+        .await
+        .unwrap();
+    console.stop_responding().await.unwrap();
+
+    assert_eq!(report.answer.as_deref(), Some("Hello, world"));
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "pondering\nHello, world\n"
+    );
+}
+
+#[tokio::test]
+async fn test_cancelled_turn() {
+    let mut agent = Agent::new(Arc::new(PendingModel));
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&b""[..], &mut output);
+
+    console.start_responding().await.unwrap();
+    let token = console.cancellation_token().unwrap();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        token.cancel();
+    });
+    let report = agent
+        .run_turn(&mut console, "never answered".to_string())
+        .await
+        .unwrap();
+    console.stop_responding().await.unwrap();
+
+    assert_eq!(report.stop, StopReason::Cancelled);
+    assert_eq!(report.steps, 1);
+    assert!(agent.history().is_empty());
+    assert!(String::from_utf8(output).unwrap().contains("Cancelled."));
+}
+
+#[tokio::test]
+async fn test_tool_activity() {
+    let model = ScriptedModel::new(vec![
+        ModelResponse {
+            tool_calls: vec![ToolCall {
+                id: "call-1".to_string(),
+                name: "run_tests".to_string(),
+                arguments: json!({}),
+            }],
+            ..ModelResponse::default()
+        },
+        answer("a test fails"),
+    ]);
+    let mut agent = Agent::new(model.clone()).with_tool(FailingTests);
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&b""[..], &mut output);
+
+    console.start_responding().await.unwrap();
+    agent
+        .run_turn(&mut console, "test it".to_string())
+        .await
+        .unwrap();
+    console.stop_responding().await.unwrap();
+
+    let requests = model.requests.lock().unwrap();
+    assert_eq!(
+        requests[1].last(),
+        Some(&ChatMessage::ToolResult {
+            call_id: "call-1".to_string(),
+            content: "Error: Tool failed: 1 test failed".to_string()
+        })
+    );
+    let output_str = String::from_utf8(output).unwrap();
+    assert!(output_str.starts_with("Action: run_tests({})\nFailed run_tests after 0."));
+    assert!(output_str.contains("s: Tool failed: 1 test failed\n"));
+}
+
+#[tokio::test]
+async fn test_permissions() {
+    let model = ScriptedModel::new(vec![
+        shell_call("call-1", "git log"),
+        shell_call("call-2", "git log --oneline"),
+        shell_call("call-3", "git clean -fd"),
+        answer("done"),
+    ]);
+    let mut agent = Agent::new(model.clone()).with_tool(Shell);
+    // the session approval of `git log *` covers the second call
+    let mut console = ScriptedConsole::new([
+        Interaction::Permission(Permission::AllowSession),
+        Interaction::Permission(Permission::Deny("keep the new files".to_string())),
+    ]);
+
+    console.start_responding().await.unwrap();
+    agent
+        .run_turn(&mut console, "check it".to_string())
+        .await
+        .unwrap();
+
+    let results: Vec<String> = model
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .skip(1)
+        .filter_map(|request| match request.last() {
+            Some(ChatMessage::ToolResult { content, .. }) => Some(content.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        results,
+        [
+            r#"ran "git log""#,
+            r#"ran "git log --oneline""#,
+            "Error: the user denied this action with the feedback: keep the new files",
+        ]
+    );
+    assert!(agent.permissions().is_allowed("shell", "git log -p"));
+    assert!(agent.permissions().saved().is_empty());
+    assert!(console.is_finished());
+    let asked: Vec<&str> = console
+        .transcript()
+        .iter()
+        .filter_map(|record| match record {
+            Record::Permission(request) => Some(request.subject.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(asked, ["git log", "git clean -fd"]);
+}
+
+#[tokio::test]
+async fn test_status() {
+    let mut agent = Agent::new(Arc::new(MeteredModel));
+    let mut console = ScriptedConsole::new([]);
+
+    agent.update_status(&mut console);
+    assert_eq!(console.status().model.as_deref(), Some("metered"));
+    assert_eq!(console.status().context_usage, Some(0));
+    assert_eq!(console.status().cost, Some(0.0));
+
+    for prompt in ["one", "two"] {
+        console.start_responding().await.unwrap();
+        agent
+            .run_turn(&mut console, prompt.to_string())
+            .await
+            .unwrap();
+        console.stop_responding().await.unwrap();
+    }
+    assert_eq!(console.status().context_usage, Some(40));
+    // 2 * (100 * $10 + 200 cached * $1 + 100 * $20) per million tokens
+    assert!((console.status().cost.unwrap() - 0.0064).abs() < 1e-9);
+
+    agent.clear_history();
+    agent.update_status(&mut console);
+    assert_eq!(console.status().context_usage, Some(0));
+}
+
+#[tokio::test]
+async fn test_statistics() {
+    let model = ScriptedModel::new(vec![
+        echo_call("call-1", json!({"text": "pong"})),
+        echo_call("call-2", json!({})),
+        answer("done"),
+    ]);
+    let mut agent = Agent::new(model).with_tool(Echo);
+    let mut console = ScriptedConsole::new([]);
+
+    console.start_responding().await.unwrap();
+    let report = agent
+        .run_turn(&mut console, "ping".to_string())
+        .await
+        .unwrap();
+    console.stop_responding().await.unwrap();
+
+    let statistics = &console.statistics()[0];
+    assert_eq!(statistics, &report.statistics);
+    assert_eq!(statistics.model_calls, 3);
+    let tool_calls: Vec<_> = statistics
+        .tool_calls
+        .iter()
+        .map(|tool_call| (tool_call.name.as_str(), tool_call.success))
+        .collect();
+    assert_eq!(tool_calls, [("echo", true), ("echo", false)]);
+    assert_eq!(statistics.cost, None);
+    assert_eq!(agent.session_statistics().tool_calls, 2);
+    assert_eq!(agent.session_statistics().cost, None);
+}
+
+#[tokio::test]
+async fn test_session_statistics() {
+    let mut agent = Agent::new(Arc::new(MeteredModel));
+    let mut console = ScriptedConsole::new([]);
+
+    for prompt in ["one", "two"] {
+        console.start_responding().await.unwrap();
+        agent
+            .run_turn(&mut console, prompt.to_string())
+            .await
+            .unwrap();
+        console.stop_responding().await.unwrap();
+    }
+    agent.clear_history();
+
+    let turn = &console.statistics()[1];
+    assert_eq!(
+        (turn.input_tokens, turn.cached_tokens, turn.output_tokens),
+        (300, 200, 100)
+    );
+    assert!((turn.cost.unwrap() - 0.0032).abs() < 1e-9);
+    let session = agent.session_statistics();
+    assert_eq!(session.turns, 2);
+    assert_eq!(session.model_calls, 2);
+    assert_eq!(
+        (
+            session.input_tokens,
+            session.cached_tokens,
+            session.output_tokens
+        ),
+        (600, 400, 200)
+    );
+    assert!((session.cost.unwrap() - 0.0064).abs() < 1e-9);
+}
@@ -0,0 +1,253 @@
+use crate::common::ConsoleError;
+use crate::input::ConsoleInput;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, LazyLock};
+
+/// How a command argument is matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgKind {
+    /// a mandatory value
+    Required,
+    /// a value that can be omitted, it must follow the mandatory arguments
+    Optional,
+    /// a mandatory value from a fixed list
+    Choice(Vec<String>),
+    /// all remaining values, possibly none
+    Rest,
+}
+
+/// The description of a command argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgSpec {
+    pub name: String,
+    pub kind: ArgKind,
+}
+
+impl ArgSpec {
+    pub fn required(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: ArgKind::Required,
+        }
+    }
+
+    pub fn optional(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: ArgKind::Optional,
+        }
+    }
+
+    pub fn choice(name: &str, values: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: ArgKind::Choice(values.iter().map(|v| v.to_string()).collect()),
+        }
+    }
+
+    pub fn rest(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: ArgKind::Rest,
+        }
+    }
+
+    fn usage(&self) -> String {
+        match &self.kind {
+            ArgKind::Required => format!("<{}>", self.name),
+            ArgKind::Optional => format!("[{}]", self.name),
+            ArgKind::Choice(values) => values.join("|"),
+            ArgKind::Rest => format!("[{}...]", self.name),
+        }
+    }
+}
+
+/// Turns the validated arguments of a command into the console input.
+pub type CommandHandler = Arc<dyn Fn(Vec<String>) -> ConsoleInput + Send + Sync>;
+
+/// A slash command: its name, arguments, help text and handler.
+///
+/// Commands without a handler are returned as `ConsoleInput::Command`, so the code that
+/// registered them can act on them.
+#[derive(Clone)]
+pub struct CommandSpec {
+    /// The name without the leading '/'.
+    pub name: String,
+    pub args: Vec<ArgSpec>,
+    pub help: String,
+    handler: Option<CommandHandler>,
+}
+
+impl CommandSpec {
+    pub fn new(name: &str, help: &str) -> Self {
+        Self {
+            name: name.trim_start_matches('/').to_string(),
+            args: Vec::new(),
+            help: help.to_string(),
+            handler: None,
+        }
+    }
+
+    pub fn arg(mut self, arg: ArgSpec) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    pub fn handler(
+        mut self,
+        handler: impl Fn(Vec<String>) -> ConsoleInput + Send + Sync + 'static,
+    ) -> Self {
+        self.handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Returns the command line synopsis, e.g. `/thinking on|off`.
+    pub fn usage(&self) -> String {
+        let mut usage = format!("/{}", self.name);
+        for arg in &self.args {
+            usage.push(' ');
+            usage.push_str(&arg.usage());
+        }
+        usage
+    }
+
+    /// Checks the arguments against the spec, returns `false` if they do not match.
+    fn matches(&self, args: &[String]) -> bool {
+        let mut rest = args.iter();
+        for spec in &self.args {
+            match &spec.kind {
+                ArgKind::Required => {
+                    if rest.next().is_none() {
+                        return false;
+                    }
+                }
+                ArgKind::Optional => {
+                    rest.next();
+                }
+                ArgKind::Choice(values) => match rest.next() {
+                    Some(value) if values.contains(value) => {}
+                    _ => return false,
+                },
+                ArgKind::Rest => return true,
+            }
+        }
+        rest.next().is_none()
+    }
+}
+
+/// The set of slash commands understood by a console.
+///
+/// `CommandRegistry::default()` contains the built-in commands: `/help`, `/exit`,
+/// `/thinking`, `/statistics` and `/status`. The partner binary and the agent add their own commands
+/// with `register`.
+#[derive(Clone)]
+pub struct CommandRegistry {
+    commands: BTreeMap<String, CommandSpec>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(
+            CommandSpec::new("help", "Show the available commands").handler(|_| ConsoleInput::Help),
+        );
+        registry
+            .register(CommandSpec::new("exit", "End the session").handler(|_| ConsoleInput::Exit));
+        registry.register(
+            CommandSpec::new("thinking", "Show or hide the agent thinking")
+                .arg(ArgSpec::choice("state", &["on", "off"]))
+                .handler(|args| ConsoleInput::Thinking(args[0] == "on")),
+        );
+        registry.register(
+            CommandSpec::new("statistics", "Show or hide the response statistics")
+                .arg(ArgSpec::choice("state", &["on", "off"]))
+                .handler(|args| ConsoleInput::Statistics(args[0] == "on")),
+        );
+        registry.register(
+            CommandSpec::new(
+                "status",
+                "Show the model, the context usage, the cost and the workspace",
+            )
+            .handler(|_| ConsoleInput::Status),
+        );
+        registry
+    }
+}
+
+static DEFAULT_REGISTRY: LazyLock<CommandRegistry> = LazyLock::new(CommandRegistry::default);
+
+impl CommandRegistry {
+    /// Creates a registry without any command, not even the built-in ones.
+    pub fn empty() -> Self {
+        Self {
+            commands: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the shared registry with the built-in commands.
+    pub fn builtin() -> &'static CommandRegistry {
+        &DEFAULT_REGISTRY
+    }
+
+    /// Adds a command, replacing any previous command with the same name.
+    pub fn register(&mut self, spec: CommandSpec) {
+        self.commands.insert(spec.name.clone(), spec);
+    }
+
+    pub fn unregister(&mut self, name: &str) -> Option<CommandSpec> {
+        self.commands.remove(name.trim_start_matches('/'))
+    }
+
+    /// Looks up a command by name, with or without the leading '/'.
+    pub fn get(&self, name: &str) -> Option<&CommandSpec> {
+        self.commands.get(name.trim_start_matches('/'))
+    }
+
+    /// Returns the commands in name order.
+    pub fn commands(&self) -> impl Iterator<Item = &CommandSpec> {
+        self.commands.values()
+    }
+
+    /// Generates the `/help` output from the registered commands.
+    pub fn help(&self) -> String {
+        let usages: Vec<String> = self.commands().map(CommandSpec::usage).collect();
+        let width = usages.iter().map(String::len).max().unwrap_or(0);
+        let mut help = String::from("Commands:");
+        for (usage, spec) in usages.iter().zip(self.commands()) {
+            let _ = write!(help, "\n  {:width$}  {}", usage, spec.help, width = width);
+        }
+        help
+    }
+
+    /// Parses a line of user input.
+    ///
+    /// Lines starting with '/' are split with shell quoting rules and matched against the
+    /// registered commands, anything else is a prompt. Returns
+    /// `ConsoleError::UnknownCommand` if the command is not registered or its arguments
+    /// don't match the spec.
+    pub fn parse(&self, line: &str) -> Result<ConsoleInput, ConsoleError> {
+        let trimmed_line = line.trim();
+        if trimmed_line.starts_with('/') {
+            if let Some(mut parts) = shlex::split(trimmed_line) {
+                if !parts.is_empty() {
+                    let command = parts.remove(0);
+                    return match self.get(&command) {
+                        Some(spec) if spec.matches(&parts) => Ok(match &spec.handler {
+                            Some(handler) => handler(parts),
+                            None => ConsoleInput::Command {
+                                name: spec.name.clone(),
+                                args: parts,
+                            },
+                        }),
+                        _ => Err(ConsoleError::UnknownCommand { command }),
+                    };
+                }
+            }
+        }
+
+        Ok(ConsoleInput::Prompt {
+            prompt: line.to_string(),
+        })
+    }
+}
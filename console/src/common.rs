@@ -1,5 +1,14 @@
 use async_trait::async_trait;
+use crate::command::CommandRegistry;
+pub use crate::diff::FileDiff;
 pub use crate::input::ConsoleInput;
+pub use crate::message::Message;
+pub use crate::permission::{Permission, PermissionRequest};
+pub use crate::selection::Choice;
+pub use crate::statistics::{SessionStatistics, ToolCallStatistics, TurnStatistics};
+pub use crate::status::{ConsoleMode, Status};
+pub use tokio_util::sync::CancellationToken;
+use std::time::Duration;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ConsoleError {
@@ -12,14 +21,32 @@ pub enum ConsoleError {
     /// The command is unknown
     #[error("Unknown command: {command}")]
     UnknownCommand { command: String },
+    /// The user cancelled the turn in flight (Ctrl-C, Esc ...)
+    #[error("Cancelled")]
+    Cancelled,
+    /// The console has no input to answer a question with, e.g. in a non-interactive run
+    #[error("No input to answer the question")]
+    NoInput,
 }
 
+/// What the consoles show besides the response.
+///
+/// `statistics` shows the report of `Console::add_statistics` at the end of each turn,
+/// `thinking` the thinking text and deltas.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct Observability {
     pub statistics: bool,
     pub thinking: bool,
 }
 
+/// How a tool call has ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolStatus {
+    Succeeded,
+    /// The tool failed with this error message
+    Failed(String),
+}
+
 /// A trait for interacting with the user console.
 ///
 /// This trait defines a standard interface for console operations, allowing for different
@@ -46,6 +73,19 @@ pub trait Console {
     /// input prompt is typically displayed again.
     async fn stop_responding(&mut self) -> Result<(), ConsoleError>;
 
+    /// Returns the cancellation signal of the turn in flight.
+    ///
+    /// A new token is created by `start_responding` and fires when the user interrupts the
+    /// response (Ctrl-C, Esc ...). Returns `None` in the prompting state. Interactive calls
+    /// waiting for the user return `ConsoleError::Cancelled` once the token fires.
+    fn cancellation_token(&self) -> Option<CancellationToken>;
+
+    /// Returns the slash commands understood by `prompt_input`.
+    ///
+    /// The registry starts with the built-in commands, callers can register their own.
+    /// `/help` is answered by the console itself from this registry.
+    fn commands(&mut self) -> &mut CommandRegistry;
+
     /// Gets or sets the observability flags for debugging and statistics.
     ///
     /// If `new_settings` is `Some`, it sets the observability flags to the given values
@@ -53,6 +93,12 @@ pub trait Console {
     /// current settings without changing them.
     fn observability(&mut self, new_settings: Option<Observability>) -> Observability;
 
+    /// Returns the session status: the model, the context usage, the cost and the workspace.
+    ///
+    /// The agent and the caller update the values they know. Consoles with a status bar
+    /// show the changes on their next redraw, the others print the status on `/status`.
+    fn status(&mut self) -> &mut Status;
+
     /// Appends a block of text to the agent's response area.
     ///
     /// This is the primary way for the agent to communicate its results to the user.
@@ -64,12 +110,86 @@ pub trait Console {
     /// is working on a response.
     async fn add_thinking_text(&mut self, text: String) -> Result<(), ConsoleError>;
 
+    /// Appends a streamed fragment to the current response.
+    ///
+    /// Unlike `add_response_text`, the fragment is not a complete block: it is rendered right
+    /// after the previous fragment, without a line break, as the model generates tokens.
+    async fn add_response_delta(&mut self, delta: String) -> Result<(), ConsoleError>;
+
+    /// Appends a streamed fragment to the current "thinking" text.
+    ///
+    /// This is the streaming counterpart of `add_thinking_text` and follows the same
+    /// observability rules.
+    async fn add_thinking_delta(&mut self, delta: String) -> Result<(), ConsoleError>;
+
+    /// Appends a typed message to the agent's response area.
+    ///
+    /// Each console renders the kinds of `Message` in its own way, e.g. with colors for
+    /// errors and diffs. The plain text form is the `Display` implementation of the message.
+    async fn add_message(&mut self, message: Message) -> Result<(), ConsoleError>;
+
+    /// Announces that the agent started a tool call.
+    ///
+    /// `arguments` is usually JSON. Only one tool call runs at a time, it ends with
+    /// `finish_tool_call`.
+    async fn start_tool_call(
+        &mut self,
+        name: String,
+        arguments: String,
+    ) -> Result<(), ConsoleError>;
+
+    /// Appends a fragment of the progress or output of the running tool call.
+    ///
+    /// An empty fragment only refreshes the display, the agent sends one regularly so
+    /// that activity indicators keep moving while a tool is silent.
+    async fn add_tool_output(&mut self, output: String) -> Result<(), ConsoleError>;
+
+    /// Reports the final status and the duration of the running tool call.
+    async fn finish_tool_call(
+        &mut self,
+        status: ToolStatus,
+        duration: Duration,
+    ) -> Result<(), ConsoleError>;
+
+    /// Shows the statistics of the turn in flight, sent by the agent once the turn ends.
+    ///
+    /// The report is only shown when the statistics are enabled in the observability flags.
+    async fn add_statistics(&mut self, statistics: TurnStatistics) -> Result<(), ConsoleError>;
+
     /// Asks the user for a confirmation on a specific action.
     ///
     /// The `text` parameter contains the question or action to be confirmed. Returns `true`
     /// if the user provides a positive confirmation (e.g., 'y' or 'yes').
     async fn if_accept(&mut self, text: String) -> Result<bool, ConsoleError>;
 
+    /// Asks the user to review the changes proposed for some files, hunk by hunk.
+    ///
+    /// Returns the accepted subset of the changes, files without accepted hunks are left
+    /// out. An empty result means that everything was rejected.
+    async fn review_diff(&mut self, files: Vec<FileDiff>) -> Result<Vec<FileDiff>, ConsoleError>;
+
+    /// Asks the user to approve an action of the agent.
+    ///
+    /// Besides allowing the action once, the user can allow the actions matching the pattern
+    /// of the request for the session or always, or deny it with feedback for the model.
+    /// Keeping and applying the approval rules is up to the caller.
+    async fn request_permission(
+        &mut self,
+        request: PermissionRequest,
+    ) -> Result<Permission, ConsoleError>;
+
+    /// Asks the user to pick among a list of options.
+    ///
+    /// Returns the index of the chosen option, or with `multiple` the indices of all the
+    /// chosen options in ascending order, possibly none. Fails with
+    /// `ConsoleError::Cancelled` when the user dismisses the prompt.
+    async fn select(
+        &mut self,
+        text: String,
+        choices: Vec<Choice>,
+        multiple: bool,
+    ) -> Result<Vec<usize>, ConsoleError>;
+
     /// Asks the user a yes/no question.
     ///
     /// The `text` parameter contains the question. Returns `true` if the user answers
@@ -0,0 +1,184 @@
+use crate::command::{ArgSpec, CommandRegistry, CommandSpec};
+use crate::input::ConsoleInput;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The placeholder replaced by the command arguments.
+pub const ARGUMENTS_PLACEHOLDER: &str = "$ARGUMENTS";
+
+/// A user-defined slash command backed by a markdown prompt template.
+///
+/// The command name is the file name without the `.md` extension. The file may start with
+/// a front matter block providing the help text:
+///
+/// ```markdown
+/// ---
+/// description: Review a file for bugs
+/// ---
+/// Review $ARGUMENTS and list the problems you find.
+/// ```
+///
+/// Without front matter, the first line of the template is used as the help text. Windows
+/// line endings are read as plain line breaks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptCommand {
+    pub name: String,
+    pub description: String,
+    pub template: String,
+    pub path: PathBuf,
+}
+
+impl PromptCommand {
+    /// Reads a prompt command from a markdown file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "invalid command file name")
+            })?;
+        let content = std::fs::read_to_string(path)?;
+        Ok(Self::parse(name, &content, path.to_path_buf()))
+    }
+
+    /// Builds a prompt command from the content of a markdown file.
+    pub fn parse(name: &str, content: &str, path: PathBuf) -> Self {
+        let content = content.replace("\r\n", "\n");
+        let mut description = None;
+        let mut template = content.as_str();
+        if let Some(front_matter) = content.strip_prefix("---\n") {
+            if let Some((header, body)) = front_matter.split_once("\n---\n") {
+                template = body;
+                description = header.lines().find_map(|line| {
+                    line.strip_prefix("description:")
+                        .map(|value| value.trim().trim_matches('"').to_string())
+                });
+            }
+        }
+        let template = template.trim().to_string();
+        let description = description.unwrap_or_else(|| {
+            template
+                .lines()
+                .map(|line| line.trim_start_matches('#').trim())
+                .find(|line| !line.is_empty())
+                .unwrap_or_default()
+                .to_string()
+        });
+        Self {
+            name: name.to_string(),
+            description,
+            template,
+            path,
+        }
+    }
+
+    /// Expands the template with the command arguments.
+    ///
+    /// Every `$ARGUMENTS` is replaced by the arguments joined with spaces. If the template
+    /// has no placeholder, non-empty arguments are appended after a blank line.
+    pub fn expand(&self, args: &[String]) -> String {
+        let arguments = args.join(" ");
+        if self.template.contains(ARGUMENTS_PLACEHOLDER) {
+            self.template.replace(ARGUMENTS_PLACEHOLDER, &arguments)
+        } else if arguments.is_empty() {
+            self.template.clone()
+        } else {
+            format!("{}\n\n{}", self.template, arguments)
+        }
+    }
+
+    /// Returns the registry entry expanding the command into a `ConsoleInput::Prompt`.
+    pub fn spec(&self) -> CommandSpec {
+        let command = self.clone();
+        CommandSpec::new(&self.name, &self.description)
+            .arg(ArgSpec::rest("arguments"))
+            .handler(move |args| ConsoleInput::Prompt {
+                prompt: command.expand(&args),
+            })
+    }
+}
+
+/// Loads all `*.md` files of a directory, a missing directory has no commands.
+///
+/// A file that can't be loaded, like an unreadable or non UTF-8 one, is skipped. The
+/// warnings about the skipped files are returned with the commands.
+pub fn load_dir(dir: &Path) -> io::Result<(Vec<PromptCommand>, Vec<String>)> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(err) => return Err(err),
+    };
+    let mut commands = Vec::new();
+    let mut warnings = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
+            match PromptCommand::load(&path) {
+                Ok(command) => commands.push(command),
+                Err(err) => warnings.push(format!(
+                    "Skipped the command file {}: {}",
+                    path.display(),
+                    err
+                )),
+            }
+        }
+    }
+    commands.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok((commands, warnings))
+}
+
+/// Returns the command directories in increasing priority order.
+///
+/// These are the user directory `~/.config/partner/commands` (or
+/// `$XDG_CONFIG_HOME/partner/commands`) and the project directory
+/// `<project>/.partner/commands`.
+pub fn default_dirs(project_root: &Path) -> Vec<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    config_home
+        .map(|config| config.join("partner").join("commands"))
+        .into_iter()
+        .chain(std::iter::once(
+            project_root.join(".partner").join("commands"),
+        ))
+        .collect()
+}
+
+/// Registers the prompt commands found in `dirs`.
+///
+/// A command from a later directory replaces a command with the same name from an earlier
+/// one. Commands already present in the registry, like the built-in ones, are kept.
+/// Returns the registered commands and the warnings about the files and directories that
+/// were skipped, since a broken command file shouldn't keep the others from working.
+pub fn register_dirs(
+    registry: &mut CommandRegistry,
+    dirs: &[PathBuf],
+) -> (Vec<PromptCommand>, Vec<String>) {
+    let mut commands = BTreeMap::new();
+    let mut warnings = Vec::new();
+    for dir in dirs {
+        match load_dir(dir) {
+            Ok((loaded, skipped)) => {
+                for command in loaded {
+                    commands.insert(command.name.clone(), command);
+                }
+                warnings.extend(skipped);
+            }
+            Err(err) => warnings.push(format!(
+                "Skipped the command directory {}: {}",
+                dir.display(),
+                err
+            )),
+        }
+    }
+    let registered: Vec<PromptCommand> = commands
+        .into_values()
+        .filter(|command| registry.get(&command.name).is_none())
+        .collect();
+    for command in &registered {
+        registry.register(command.spec());
+    }
+    (registered, warnings)
+}
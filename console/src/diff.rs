@@ -0,0 +1,175 @@
+use serde::Serialize;
+use std::fmt;
+
+/// A hunk of a unified diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hunk {
+    /// the `@@ -a,b +c,d @@` line
+    pub header: String,
+    /// the lines of the hunk with their ` `, `+` or `-` prefix
+    pub lines: Vec<String>,
+}
+
+impl Hunk {
+    /// Returns the start and the number of lines of the old and the new range of the
+    /// header, `None` if it is not a valid `@@ -a,b +c,d @@` line.
+    fn ranges(&self) -> Option<((usize, usize), (usize, usize))> {
+        let mut ranges = self.header.strip_prefix("@@ ")?.split_whitespace();
+        let range = |range: &str| -> Option<(usize, usize)> {
+            match range.split_once(',') {
+                Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+                None => Some((range.parse().ok()?, 1)),
+            }
+        };
+        let old = range(ranges.next()?.strip_prefix('-')?)?;
+        let new = range(ranges.next()?.strip_prefix('+')?)?;
+        Some((old, new))
+    }
+}
+
+/// The changes proposed for one file, as the hunks of a unified diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    /// Parses a unified diff of one or more files, like the output of `git diff`.
+    ///
+    /// Lines before the first `---`/`+++` pair of a file, like the `diff --git` and
+    /// `index` lines, are ignored. The path is the new one, without the `b/` prefix.
+    ///
+    /// A hunk takes as many lines as its header counts, so a removed `-- ` line followed
+    /// by an added `++ ` line is not taken for the header of a file.
+    pub fn parse(text: &str) -> Vec<FileDiff> {
+        let mut files: Vec<FileDiff> = Vec::new();
+        let mut lines = text.lines().peekable();
+        // the old and the new lines still expected in the current hunk
+        let mut remaining: (usize, usize) = (0, 0);
+        while let Some(line) = lines.next() {
+            if remaining != (0, 0) {
+                if let Some(hunk) = files.last_mut().and_then(|file| file.hunks.last_mut()) {
+                    let (old, new) = remaining;
+                    let counted = match line.chars().next() {
+                        Some('-') => Some((old.saturating_sub(1), new)),
+                        Some('+') => Some((old, new.saturating_sub(1))),
+                        Some(' ') | None => Some((old.saturating_sub(1), new.saturating_sub(1))),
+                        Some('\\') => Some(remaining),
+                        // the counts of the header are wrong, the hunk ends here
+                        _ => None,
+                    };
+                    remaining = counted.unwrap_or_default();
+                    if counted.is_some() {
+                        hunk.lines.push(line.to_string());
+                        continue;
+                    }
+                }
+            }
+            if let Some(old_path) = line.strip_prefix("--- ") {
+                if let Some(new_path) = lines.peek().and_then(|next| next.strip_prefix("+++ ")) {
+                    let path = match new_path.trim() {
+                        "/dev/null" => old_path.trim(),
+                        path => path,
+                    };
+                    let path = path
+                        .strip_prefix("a/")
+                        .or_else(|| path.strip_prefix("b/"))
+                        .unwrap_or(path);
+                    files.push(FileDiff {
+                        path: path.to_string(),
+                        hunks: Vec::new(),
+                    });
+                    lines.next();
+                    continue;
+                }
+            }
+            let Some(file) = files.last_mut() else {
+                continue;
+            };
+            if line.starts_with("@@") {
+                let hunk = Hunk {
+                    header: line.to_string(),
+                    lines: Vec::new(),
+                };
+                remaining = hunk.ranges().map_or((0, 0), |(old, new)| (old.1, new.1));
+                file.hunks.push(hunk);
+            } else if let Some(hunk) = file.hunks.last_mut() {
+                if line.starts_with([' ', '+', '-', '\\']) || line.is_empty() {
+                    hunk.lines.push(line.to_string());
+                }
+            }
+        }
+        files
+    }
+
+    /// Returns `true` if the diff creates the file, its only hunk has no old lines.
+    pub fn is_new(&self) -> bool {
+        self.has_only_range(|(old, _)| old)
+    }
+
+    /// Returns `true` if the diff deletes the file, its only hunk has no new lines.
+    pub fn is_deleted(&self) -> bool {
+        self.has_only_range(|(_, new)| new)
+    }
+
+    fn has_only_range(
+        &self,
+        side: impl Fn(((usize, usize), (usize, usize))) -> (usize, usize),
+    ) -> bool {
+        matches!(
+            self.hunks.as_slice(),
+            [hunk] if hunk.ranges().map(side) == Some((0, 0))
+        )
+    }
+}
+
+impl fmt::Display for FileDiff {
+    /// Formats the diff in the unified format, accepted by `git apply` and `patch -p1`.
+    ///
+    /// The old path of a new file and the new path of a deleted one are `/dev/null`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let old = if self.is_new() {
+            "/dev/null".to_string()
+        } else {
+            format!("a/{}", self.path)
+        };
+        let new = if self.is_deleted() {
+            "/dev/null".to_string()
+        } else {
+            format!("b/{}", self.path)
+        };
+        write!(f, "--- {}\n+++ {}", old, new)?;
+        for hunk in &self.hunks {
+            write!(f, "\n{}", hunk.header)?;
+            for line in &hunk.lines {
+                write!(f, "\n{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the hunks accepted by a review, `accepted` has one flag per hunk of each file.
+///
+/// Files without accepted hunks are left out. The hunk headers are kept as they are, so
+/// the line numbers of the hunks following a rejected one may be off by its length.
+pub fn accepted_hunks(files: Vec<FileDiff>, accepted: &[Vec<bool>]) -> Vec<FileDiff> {
+    files
+        .into_iter()
+        .zip(accepted)
+        .filter_map(|(file, accepted)| {
+            let hunks: Vec<Hunk> = file
+                .hunks
+                .into_iter()
+                .zip(accepted)
+                .filter(|(_, accepted)| **accepted)
+                .map(|(hunk, _)| hunk)
+                .collect();
+            (!hunks.is_empty()).then_some(FileDiff {
+                path: file.path,
+                hunks,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,159 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+
+/// The number of entries kept when a history file is loaded.
+pub const DEFAULT_MAX_ENTRIES: usize = 1000;
+
+/// The prompt history shared by the consoles.
+///
+/// Entries are kept oldest first. A history loaded from a file appends every new entry to
+/// it, one entry per line, with line breaks and backslashes escaped so that multi-line
+/// prompts survive the round trip.
+///
+/// It also keeps the navigation state used by Up/Down: the entry being shown and the
+/// draft that was being edited before the navigation started.
+#[derive(Debug, Clone)]
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+    max_entries: usize,
+    position: Option<usize>,
+    draft: String,
+}
+
+fn escape(entry: &str) -> String {
+    entry.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut entry = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.next_if_eq(&'n').is_some() => entry.push('\n'),
+            '\\' if chars.next_if_eq(&'\\').is_some() => entry.push('\\'),
+            _ => entry.push(c),
+        }
+    }
+    entry
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl History {
+    /// Creates a history that is not saved anywhere.
+    pub fn in_memory() -> Self {
+        Self {
+            entries: Vec::new(),
+            path: None,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            position: None,
+            draft: String::new(),
+        }
+    }
+
+    /// Loads the history file, a missing file is an empty history.
+    ///
+    /// Only the last `DEFAULT_MAX_ENTRIES` entries are kept, new entries are appended to
+    /// the file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let mut entries: Vec<String> = content.lines().map(unescape).collect();
+        entries.drain(..entries.len().saturating_sub(DEFAULT_MAX_ENTRIES));
+        Ok(Self {
+            entries,
+            path: Some(path.to_path_buf()),
+            ..Self::in_memory()
+        })
+    }
+
+    /// Returns the history file of a project, `<project>/.partner/history`.
+    pub fn default_path(project_root: &Path) -> PathBuf {
+        project_root.join(".partner").join("history")
+    }
+
+    /// Returns the entries, oldest first.
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Adds an entry and resets the navigation.
+    ///
+    /// Blank entries and repetitions of the last entry are ignored. The entry is appended
+    /// to the history file, creating it and its directory if needed.
+    pub fn add(&mut self, entry: &str) -> io::Result<()> {
+        self.position = None;
+        self.draft.clear();
+        if entry.trim().is_empty() || self.entries.last().is_some_and(|last| last == entry) {
+            return Ok(());
+        }
+        self.entries.push(entry.to_string());
+        if self.entries.len() > self.max_entries {
+            self.entries.remove(0);
+        }
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", escape(entry))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the entry before the one being shown, `current` is the text being edited.
+    ///
+    /// The first call saves `current` as the draft restored by `next_entry`. Returns `None` at
+    /// the oldest entry.
+    pub fn previous_entry(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(0) => return None,
+            Some(position) => position - 1,
+        };
+        self.position = Some(position);
+        Some(&self.entries[position])
+    }
+
+    /// Returns the entry after the one being shown, or the saved draft after the newest
+    /// entry. Returns `None` if no entry is being shown.
+    pub fn next_entry(&mut self) -> Option<&str> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            Some(&self.entries[position + 1])
+        } else {
+            self.position = None;
+            Some(&self.draft)
+        }
+    }
+
+    /// Forgets the entry being shown, e.g. when the input is cleared.
+    pub fn reset_navigation(&mut self) {
+        self.position = None;
+    }
+
+    /// Finds the newest entry containing `query` among the entries before `before`.
+    pub fn search(&self, query: &str, before: usize) -> Option<usize> {
+        self.entries[..before.min(self.entries.len())]
+            .iter()
+            .rposition(|entry| entry.contains(query))
+    }
+}
@@ -1,3 +1,4 @@
+use crate::command::CommandRegistry;
 use crate::common::ConsoleError;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -7,41 +8,23 @@ pub enum ConsoleInput {
     Prompt { prompt: String },
     /// the command /exit
     Exit,
+    /// the command /help, consoles answer it with `CommandRegistry::help`
+    Help,
     /// the command /thinking on/off
     Thinking(bool),
     /// the command /statistics on/off
     Statistics(bool),
+    /// the command /status, consoles answer it with `Status::line`
+    Status,
+    /// a registered command without its own handler
+    Command { name: String, args: Vec<String> },
     /// any unknown command starting with '/' symbol
     UnknownCommand { command: String },
 }
 
 impl ConsoleInput {
+    /// Parses a line with the built-in commands only, see `CommandRegistry::parse`.
     pub fn from_line(line: &str) -> Result<Self, ConsoleError> {
-        let trimmed_line = line.trim();
-        if trimmed_line.starts_with('/') {
-            if let Some(parts) = shlex::split(trimmed_line) {
-                if !parts.is_empty() {
-                    let command = parts[0].clone();
-                    return match parts.as_slice() {
-                        [cmd, arg] if cmd.as_str() == "/thinking" => match arg.as_str() {
-                            "on" => Ok(ConsoleInput::Thinking(true)),
-                            "off" => Ok(ConsoleInput::Thinking(false)),
-                            _ => Err(ConsoleError::UnknownCommand { command }),
-                        },
-                        [cmd, arg] if cmd.as_str() == "/statistics" => match arg.as_str() {
-                            "on" => Ok(ConsoleInput::Statistics(true)),
-                            "off" => Ok(ConsoleInput::Statistics(false)),
-                            _ => Err(ConsoleError::UnknownCommand { command }),
-                        },
-                        [cmd] if cmd.as_str() == "/exit" => Ok(ConsoleInput::Exit),
-                        _ => Err(ConsoleError::UnknownCommand { command }),
-                    };
-                }
-            }
-        }
-
-        Ok(ConsoleInput::Prompt {
-            prompt: line.to_string(),
-        })
+        CommandRegistry::builtin().parse(line)
     }
 }
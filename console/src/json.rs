@@ -0,0 +1,689 @@
+use crate::command::CommandRegistry;
+use crate::common::{
+    CancellationToken, Choice, Console, ConsoleError, ConsoleInput, ConsoleMode, FileDiff, Message,
+    Observability, Permission, PermissionRequest, Status, ToolStatus, TurnStatistics,
+};
+use crate::diff::accepted_hunks;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt as _, AsyncRead, AsyncWrite, AsyncWriteExt as _, BufReader};
+use tokio::sync::mpsc;
+
+/// A line read by the JSON console, told apart by its `type` field.
+///
+/// `prompt` and `command` answer an `awaiting_input` event, the other requests answer the
+/// question events of the same name. `cancel` interrupts the turn in flight.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    /// `{"type": "prompt", "text": "..."}`, a text starting with `/` is a command line
+    Prompt { text: String },
+    /// `{"type": "command", "name": "model", "args": []}`
+    Command {
+        name: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// `{"type": "exit"}`
+    Exit,
+    /// `{"type": "confirm", "answer": true}`
+    Confirm { answer: bool },
+    /// `{"type": "answer", "text": "..."}`
+    Answer { text: String },
+    /// `{"type": "permission", "decision": "allow_session"}`, the feedback goes with `deny`
+    Permission {
+        decision: Decision,
+        #[serde(default)]
+        feedback: String,
+    },
+    /// `{"type": "select", "indices": [0, 2]}`
+    Select { indices: Vec<usize> },
+    /// `{"type": "review", "accepted": [[true, false], [true]]}`, one flag per hunk
+    Review { accepted: Vec<Vec<bool>> },
+    /// `{"type": "cancel"}`
+    Cancel,
+}
+
+/// The decision of a `permission` request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Decision {
+    AllowOnce,
+    AllowSession,
+    AllowAlways,
+    Deny,
+}
+
+/// A line written by the JSON console, told apart by its `type` field.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Event<'a> {
+    /// the console waits for a `prompt`, `command` or `exit` request
+    AwaitingInput,
+    Help {
+        text: &'a str,
+    },
+    /// the fields of the status with the mode of the console
+    Status {
+        #[serde(flatten)]
+        status: &'a Status,
+        mode: ConsoleMode,
+    },
+    ResponseStart,
+    /// `duration_ms` is only set when statistics are enabled
+    ResponseEnd {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        duration_ms: Option<u128>,
+    },
+    ResponseText {
+        text: &'a str,
+    },
+    ResponseDelta {
+        delta: &'a str,
+    },
+    ThinkingText {
+        text: &'a str,
+    },
+    ThinkingDelta {
+        delta: &'a str,
+    },
+    Message {
+        message: &'a Message,
+    },
+    ToolStart {
+        name: &'a str,
+        arguments: &'a str,
+    },
+    ToolOutput {
+        output: &'a str,
+    },
+    ToolFinish {
+        name: &'a str,
+        success: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'a str>,
+        duration_ms: u128,
+    },
+    Statistics {
+        statistics: &'a TurnStatistics,
+    },
+    /// a yes/no question, `default` is the answer suggested to the user
+    Confirm {
+        text: &'a str,
+        default: bool,
+    },
+    Question {
+        text: &'a str,
+    },
+    Review {
+        files: &'a [FileDiff],
+    },
+    Permission {
+        request: &'a PermissionRequest,
+    },
+    Select {
+        text: &'a str,
+        choices: &'a [Choice],
+        multiple: bool,
+    },
+    /// a request that could not be parsed or was not expected, it is ignored
+    Error {
+        message: &'a str,
+    },
+}
+
+/// A request read by the reading task, or the error message of a line that isn't one.
+type RequestLine = Result<Request, String>;
+
+/// Reads the requests in the background, so that a `cancel` request or the end of the
+/// input interrupts the turn in flight even when no question is pending.
+///
+/// Both take effect once the requests read before them were taken, they cancel the current
+/// turn if there is one. A `cancel` request outside of a turn is passed on like the others,
+/// and the reading stops after an `exit` request.
+async fn read_requests<R>(
+    reader: BufReader<R>,
+    sender: mpsc::Sender<RequestLine>,
+    current: Arc<Mutex<Option<CancellationToken>>>,
+) where
+    R: AsyncRead + Unpin,
+{
+    let cancel_current = || {
+        let token = current.lock().unwrap().clone();
+        token.map(|token| token.cancel()).is_some()
+    };
+    let mut lines = reader.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request =
+            serde_json::from_str(&line).map_err(|err| format!("invalid request: {}", err));
+        let Ok(permit) = sender.reserve().await else {
+            return;
+        };
+        if matches!(request, Ok(Request::Cancel)) && cancel_current() {
+            continue;
+        }
+        let exit = matches!(request, Ok(Request::Exit));
+        permit.send(request);
+        if exit {
+            return;
+        }
+    }
+    if sender.reserve().await.is_ok() {
+        cancel_current();
+    }
+}
+
+#[derive(Debug)]
+struct RespondingState {
+    start_time: Option<Instant>,
+    cancellation: CancellationToken,
+    /// the name of the running tool call
+    tool_call: Option<String>,
+}
+
+#[derive(Debug)]
+enum State {
+    Prompting,
+    Responding(RespondingState),
+}
+
+/// A `Console` implementation speaking newline-delimited JSON, for editors and scripts.
+///
+/// Every line read is a JSON object, a request with a `type` field like
+/// `{"type": "prompt", "text": "fix the tests"}`. Every console call writes one JSON
+/// object per line, an event like `{"type": "response_delta", "delta": "Done"}`. Questions
+/// are events too, answered by the next request, and invalid or unexpected requests are
+/// reported with an `error` event and skipped. Thinking and statistics follow the
+/// observability flags, like with the other consoles.
+///
+/// The requests are read in the background from the first response on, a `cancel` request
+/// or the end of the input cancels the turn in flight.
+pub struct JsonLines<R, W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    /// the input, until the reading task takes it
+    reader: Option<BufReader<R>>,
+    requests: Option<mpsc::Receiver<RequestLine>>,
+    /// the token of the turn in flight, cancelled by the reading task
+    current: Arc<Mutex<Option<CancellationToken>>>,
+    writer: W,
+    state: State,
+    observability: Observability,
+    status: Status,
+    commands: CommandRegistry,
+}
+
+impl JsonLines<tokio::io::Stdin, tokio::io::Stdout> {
+    /// Creates a new `JsonLines` instance that reads from `stdin` and writes to `stdout`.
+    pub fn new() -> Self {
+        Self::new_with_buffers(tokio::io::stdin(), tokio::io::stdout())
+    }
+}
+
+impl Default for JsonLines<tokio::io::Stdin, tokio::io::Stdout> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: AsyncWrite + Unpin> JsonLines<tokio::io::Empty, W> {
+    /// Creates a `JsonLines` instance without input, every question fails with
+    /// `ConsoleError::NoInput` and nothing interrupts a turn.
+    pub fn write_only(writer: W) -> Self {
+        Self {
+            reader: None,
+            ..Self::new_with_buffers(tokio::io::empty(), writer)
+        }
+    }
+}
+
+impl<R, W> JsonLines<R, W>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin,
+{
+    /// Creates a new `JsonLines` instance with the given reader and writer buffers.
+    pub fn new_with_buffers(reader: R, writer: W) -> Self {
+        Self {
+            reader: Some(BufReader::new(reader)),
+            requests: None,
+            current: Arc::default(),
+            writer,
+            state: State::Prompting,
+            observability: Observability::default(),
+            status: Status::default(),
+            commands: CommandRegistry::default(),
+        }
+    }
+
+    /// Writes an event on its own line.
+    async fn emit(&mut self, event: &Event<'_>) -> Result<(), ConsoleError> {
+        let mut line = serde_json::to_vec(event).map_err(|_| ConsoleError::Terminated)?;
+        line.push(b'\n');
+        self.writer
+            .write_all(&line)
+            .await
+            .map_err(|_| ConsoleError::Terminated)?;
+        self.writer
+            .flush()
+            .await
+            .map_err(|_| ConsoleError::Terminated)
+    }
+
+    /// Returns the requests read in the background, starting the reading task on the
+    /// first call. `None` means there is no input.
+    fn requests(&mut self) -> Option<&mut mpsc::Receiver<RequestLine>> {
+        if let Some(reader) = self.reader.take() {
+            let (sender, receiver) = mpsc::channel(1);
+            tokio::spawn(read_requests(reader, sender, self.current.clone()));
+            self.requests = Some(receiver);
+        }
+        self.requests.as_mut()
+    }
+
+    /// Reads the next request, blank lines are skipped and invalid ones are reported.
+    ///
+    /// Fails with `ConsoleError::Cancelled` as soon as the turn in flight is cancelled.
+    async fn read_request(&mut self) -> Result<Request, ConsoleError> {
+        let cancellation = match &self.state {
+            State::Responding(responding_state) => responding_state.cancellation.clone(),
+            State::Prompting => CancellationToken::new(),
+        };
+        loop {
+            let Some(requests) = self.requests() else {
+                return Err(ConsoleError::NoInput);
+            };
+            let line = tokio::select! {
+                biased;
+                _ = cancellation.cancelled() => return Err(ConsoleError::Cancelled),
+                line = requests.recv() => line,
+            };
+            match line {
+                None => return Err(ConsoleError::Terminated),
+                Some(Ok(request)) => return Ok(request),
+                Some(Err(message)) => self.emit(&Event::Error { message: &message }).await?,
+            }
+        }
+    }
+
+    /// Reports a request that doesn't answer the pending event.
+    async fn unexpected(&mut self, expected: &str) -> Result<(), ConsoleError> {
+        self.emit(&Event::Error {
+            message: &format!("unexpected request, expected {}", expected),
+        })
+        .await
+    }
+
+    /// Fails with `ConsoleError::Cancelled` if the turn in flight was interrupted.
+    fn check_cancelled(&self) -> Result<(), ConsoleError> {
+        match &self.state {
+            State::Responding(responding_state) if responding_state.cancellation.is_cancelled() => {
+                Err(ConsoleError::Cancelled)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Asks a yes/no question with a `confirm` event.
+    async fn confirm(&mut self, text: &str, default: bool) -> Result<bool, ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.check_cancelled()?;
+        self.emit(&Event::Confirm { text, default }).await?;
+        loop {
+            match self.read_request().await? {
+                Request::Confirm { answer } => return Ok(answer),
+                Request::Cancel => return Err(ConsoleError::Cancelled),
+                _ => self.unexpected("confirm").await?,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<R, W> Console for JsonLines<R, W>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send,
+{
+    /// Writes an `awaiting_input` event and reads a `prompt`, `command` or `exit` request.
+    ///
+    /// Both prompts and commands are parsed with the command registry, `/help` and `/status`
+    /// are answered with a `help` or a `status` event before waiting for the next request.
+    async fn prompt_input(&mut self) -> Result<ConsoleInput, ConsoleError> {
+        if !matches!(self.state, State::Prompting) {
+            return Err(ConsoleError::InvalidState);
+        }
+        loop {
+            self.emit(&Event::AwaitingInput).await?;
+            let line = match self.read_request().await? {
+                Request::Prompt { text } => text,
+                Request::Command { name, args } => {
+                    let command = format!("/{}", name.trim_start_matches('/'));
+                    let words =
+                        std::iter::once(command.as_str()).chain(args.iter().map(String::as_str));
+                    match shlex::try_join(words) {
+                        Ok(line) => line,
+                        Err(err) => {
+                            self.emit(&Event::Error {
+                                message: &format!("invalid command: {}", err),
+                            })
+                            .await?;
+                            continue;
+                        }
+                    }
+                }
+                Request::Exit => return Ok(ConsoleInput::Exit),
+                _ => {
+                    self.unexpected("prompt, command or exit").await?;
+                    continue;
+                }
+            };
+            match self.commands.parse(&line)? {
+                ConsoleInput::Help => {
+                    let help = self.commands.help();
+                    self.emit(&Event::Help { text: &help }).await?;
+                }
+                ConsoleInput::Status => {
+                    let status = self.status.clone();
+                    self.emit(&Event::Status {
+                        status: &status,
+                        mode: ConsoleMode::Prompting,
+                    })
+                    .await?;
+                }
+                input => return Ok(input),
+            }
+        }
+    }
+
+    /// Writes a `response_start` event and starts watching the input for a `cancel`
+    /// request.
+    async fn start_responding(&mut self) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Prompting) {
+            return Err(ConsoleError::InvalidState);
+        }
+        let cancellation = CancellationToken::new();
+        *self.current.lock().unwrap() = Some(cancellation.clone());
+        self.requests();
+        self.state = State::Responding(RespondingState {
+            start_time: self.observability.statistics.then(Instant::now),
+            cancellation,
+            tool_call: None,
+        });
+        self.emit(&Event::ResponseStart).await
+    }
+
+    /// Writes a `response_end` event, with the response time if statistics are enabled.
+    async fn stop_responding(&mut self) -> Result<(), ConsoleError> {
+        let State::Responding(responding_state) = &self.state else {
+            return Err(ConsoleError::InvalidState);
+        };
+        let duration_ms = responding_state
+            .start_time
+            .map(|start_time| start_time.elapsed().as_millis());
+        self.state = State::Prompting;
+        *self.current.lock().unwrap() = None;
+        self.emit(&Event::ResponseEnd { duration_ms }).await
+    }
+
+    /// Returns the token of the current response, a client interrupts a turn with a
+    /// `cancel` request or by closing the input.
+    fn cancellation_token(&self) -> Option<CancellationToken> {
+        match &self.state {
+            State::Responding(responding_state) => Some(responding_state.cancellation.clone()),
+            State::Prompting => None,
+        }
+    }
+
+    fn commands(&mut self) -> &mut CommandRegistry {
+        &mut self.commands
+    }
+
+    fn observability(&mut self, new_settings: Option<Observability>) -> Observability {
+        let old = self.observability;
+        if let Some(new_val) = new_settings {
+            self.observability = new_val;
+        }
+        old
+    }
+
+    fn status(&mut self) -> &mut Status {
+        &mut self.status
+    }
+
+    async fn add_response_text(&mut self, text: String) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.emit(&Event::ResponseText { text: &text }).await
+    }
+
+    async fn add_thinking_text(&mut self, text: String) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        if !self.observability.thinking {
+            return Ok(());
+        }
+        self.emit(&Event::ThinkingText { text: &text }).await
+    }
+
+    async fn add_response_delta(&mut self, delta: String) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.emit(&Event::ResponseDelta { delta: &delta }).await
+    }
+
+    async fn add_thinking_delta(&mut self, delta: String) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        if !self.observability.thinking {
+            return Ok(());
+        }
+        self.emit(&Event::ThinkingDelta { delta: &delta }).await
+    }
+
+    /// Writes a `message` event with the kind and the content of the message.
+    async fn add_message(&mut self, message: Message) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.emit(&Event::Message { message: &message }).await
+    }
+
+    async fn start_tool_call(
+        &mut self,
+        name: String,
+        arguments: String,
+    ) -> Result<(), ConsoleError> {
+        let State::Responding(responding_state) = &mut self.state else {
+            return Err(ConsoleError::InvalidState);
+        };
+        if responding_state.tool_call.is_some() {
+            return Err(ConsoleError::InvalidState);
+        }
+        responding_state.tool_call = Some(name.clone());
+        self.emit(&Event::ToolStart {
+            name: &name,
+            arguments: &arguments,
+        })
+        .await
+    }
+
+    /// Writes a `tool_output` event, the empty refresh fragments are left out.
+    async fn add_tool_output(&mut self, output: String) -> Result<(), ConsoleError> {
+        if !matches!(
+            self.state,
+            State::Responding(RespondingState {
+                tool_call: Some(_),
+                ..
+            })
+        ) {
+            return Err(ConsoleError::InvalidState);
+        }
+        if output.is_empty() {
+            return Ok(());
+        }
+        self.emit(&Event::ToolOutput { output: &output }).await
+    }
+
+    async fn finish_tool_call(
+        &mut self,
+        status: ToolStatus,
+        duration: Duration,
+    ) -> Result<(), ConsoleError> {
+        let State::Responding(responding_state) = &mut self.state else {
+            return Err(ConsoleError::InvalidState);
+        };
+        let Some(name) = responding_state.tool_call.take() else {
+            return Err(ConsoleError::InvalidState);
+        };
+        let error = match &status {
+            ToolStatus::Succeeded => None,
+            ToolStatus::Failed(error) => Some(error.as_str()),
+        };
+        self.emit(&Event::ToolFinish {
+            name: &name,
+            success: error.is_none(),
+            error,
+            duration_ms: duration.as_millis(),
+        })
+        .await
+    }
+
+    /// Writes a `statistics` event if statistics are enabled, the durations are in
+    /// milliseconds.
+    async fn add_statistics(&mut self, statistics: TurnStatistics) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        if !self.observability.statistics {
+            return Ok(());
+        }
+        self.emit(&Event::Statistics {
+            statistics: &statistics,
+        })
+        .await
+    }
+
+    /// Writes a `confirm` event defaulting to `true` and reads the `confirm` answer.
+    async fn if_accept(&mut self, text: String) -> Result<bool, ConsoleError> {
+        self.confirm(&text, true).await
+    }
+
+    /// Writes a `review` event with the files and reads the `review` answer, hunks without
+    /// a flag are rejected.
+    async fn review_diff(&mut self, files: Vec<FileDiff>) -> Result<Vec<FileDiff>, ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.check_cancelled()?;
+        self.emit(&Event::Review { files: &files }).await?;
+        loop {
+            match self.read_request().await? {
+                Request::Review { accepted } => return Ok(accepted_hunks(files, &accepted)),
+                Request::Cancel => return Err(ConsoleError::Cancelled),
+                _ => self.unexpected("review").await?,
+            }
+        }
+    }
+
+    /// Writes a `permission` event with the request and reads the `permission` answer.
+    async fn request_permission(
+        &mut self,
+        request: PermissionRequest,
+    ) -> Result<Permission, ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.check_cancelled()?;
+        self.emit(&Event::Permission { request: &request }).await?;
+        loop {
+            match self.read_request().await? {
+                Request::Permission { decision, feedback } => {
+                    return Ok(match decision {
+                        Decision::AllowOnce => Permission::AllowOnce,
+                        Decision::AllowSession => Permission::AllowSession,
+                        Decision::AllowAlways => Permission::AllowAlways,
+                        Decision::Deny => Permission::Deny(feedback),
+                    });
+                }
+                Request::Cancel => return Err(ConsoleError::Cancelled),
+                _ => self.unexpected("permission").await?,
+            }
+        }
+    }
+
+    /// Writes a `select` event and reads the `select` answer, indices out of range or the
+    /// wrong number of indices are reported and asked again.
+    async fn select(
+        &mut self,
+        text: String,
+        choices: Vec<Choice>,
+        multiple: bool,
+    ) -> Result<Vec<usize>, ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) || choices.is_empty() {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.check_cancelled()?;
+        self.emit(&Event::Select {
+            text: &text,
+            choices: &choices,
+            multiple,
+        })
+        .await?;
+        loop {
+            match self.read_request().await? {
+                Request::Select { mut indices }
+                    if indices.iter().all(|index| *index < choices.len())
+                        && (multiple || indices.len() == 1) =>
+                {
+                    indices.sort_unstable();
+                    indices.dedup();
+                    return Ok(indices);
+                }
+                Request::Select { .. } => {
+                    self.emit(&Event::Error {
+                        message: &format!(
+                            "expected {} between 0 and {}",
+                            if multiple { "indices" } else { "one index" },
+                            choices.len() - 1
+                        ),
+                    })
+                    .await?
+                }
+                Request::Cancel => return Err(ConsoleError::Cancelled),
+                _ => self.unexpected("select").await?,
+            }
+        }
+    }
+
+    /// Writes a `confirm` event defaulting to `false` and reads the `confirm` answer.
+    async fn if_yes(&mut self, text: String) -> Result<bool, ConsoleError> {
+        self.confirm(&text, false).await
+    }
+
+    /// Writes a `question` event and reads the `answer`.
+    async fn ask_user(&mut self, text: String) -> Result<String, ConsoleError> {
+        self.check_cancelled()?;
+        self.emit(&Event::Question { text: &text }).await?;
+        loop {
+            match self.read_request().await? {
+                Request::Answer { text } => return Ok(text),
+                Request::Cancel => return Err(ConsoleError::Cancelled),
+                _ => self.unexpected("answer").await?,
+            }
+        }
+    }
+}
@@ -1,4 +1,15 @@
+pub mod command;
 pub mod common;
+pub mod custom;
+pub mod diff;
+pub mod history;
 pub mod input;
+pub mod json;
+pub mod message;
+pub mod permission;
+pub mod scripted;
+pub mod selection;
+pub mod statistics;
+pub mod status;
 pub mod stdio;
 pub mod terminal;
@@ -0,0 +1,108 @@
+use serde::Serialize;
+use std::fmt;
+
+/// A typed message shown by a console.
+///
+/// Consoles render each kind in their own way, e.g. the terminal colors errors and diffs
+/// and collapses long tool results. The `Display` implementation is the plain text form,
+/// used by consoles without a dedicated rendering. It is serialized as an object with the
+/// `kind` of the message and its `content`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "content", rename_all = "snake_case")]
+pub enum Message {
+    /// markdown text, like the answers of the model
+    Text(String),
+    /// a code snippet
+    Code {
+        language: Option<String>,
+        code: String,
+    },
+    /// a unified diff, optionally of a single file
+    Diff {
+        path: Option<String>,
+        diff: String,
+    },
+    /// a tool invocation, `arguments` is usually JSON
+    ToolCall {
+        name: String,
+        arguments: String,
+    },
+    /// the output of a tool invocation
+    ToolResult {
+        name: String,
+        output: String,
+        is_error: bool,
+    },
+    Error(String),
+    Warning(String),
+    Table {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+}
+
+/// Shortens a text to at most `max_chars` characters on a single line, ending it with an
+/// ellipsis if anything was cut.
+pub(crate) fn shorten(text: &str, max_chars: usize) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.len() == text.len() && line.chars().count() <= max_chars {
+        return line.to_string();
+    }
+    let mut short: String = line.chars().take(max_chars.saturating_sub(1)).collect();
+    short.push('…');
+    short
+}
+
+/// Writes a table row in the markdown syntax, escaping the cell separators.
+fn write_row(f: &mut fmt::Formatter<'_>, cells: &[String]) -> fmt::Result {
+    write!(f, "|")?;
+    for cell in cells {
+        write!(f, " {} |", cell.replace('|', "\\|").replace('\n', " "))?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Message {
+    /// Formats the message as plain text, code blocks and tables use the markdown syntax.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::Text(text) => write!(f, "{}", text),
+            Message::Code { language, code } => write!(
+                f,
+                "```{}\n{}\n```",
+                language.as_deref().unwrap_or_default(),
+                code.trim_end_matches('\n')
+            ),
+            Message::Diff {
+                path: Some(path),
+                diff,
+            } => write!(f, "{}:\n{}", path, diff.trim_end_matches('\n')),
+            Message::Diff { path: None, diff } => write!(f, "{}", diff.trim_end_matches('\n')),
+            Message::ToolCall { name, arguments } => write!(f, "Action: {}({})", name, arguments),
+            Message::ToolResult {
+                output,
+                is_error: false,
+                ..
+            } => write!(f, "Observation: {}", output),
+            Message::ToolResult {
+                output,
+                is_error: true,
+                ..
+            } => write!(f, "Observation: Error: {}", output),
+            Message::Error(text) => write!(f, "Error: {}", text),
+            Message::Warning(text) => write!(f, "Warning: {}", text),
+            Message::Table { headers, rows } => {
+                write_row(f, headers)?;
+                write!(f, "\n|")?;
+                for _ in headers {
+                    write!(f, "---|")?;
+                }
+                for row in rows {
+                    writeln!(f)?;
+                    write_row(f, row)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use serde::Serialize;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+
+/// An action of the agent that needs the approval of the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PermissionRequest {
+    /// the name of the tool
+    pub tool: String,
+    /// what the tool is about to do, e.g. a command line or a file path
+    pub subject: String,
+    /// the pattern offered for the session and project wide approvals
+    pub pattern: String,
+}
+
+impl PermissionRequest {
+    /// Creates a request with the pattern suggested by `Rule::suggest`.
+    pub fn new(tool: String, subject: String) -> Self {
+        let pattern = Rule::suggest(&subject);
+        Self {
+            tool,
+            subject,
+            pattern,
+        }
+    }
+
+    /// Returns the rule approving the pattern of the request.
+    pub fn rule(&self) -> Rule {
+        Rule {
+            tool: self.tool.clone(),
+            pattern: self.pattern.clone(),
+        }
+    }
+}
+
+/// The answer of the user to a `PermissionRequest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Permission {
+    /// allow this action only
+    AllowOnce,
+    /// allow the actions matching the pattern of the request until the session ends
+    AllowSession,
+    /// allow the actions matching the pattern of the request in this project from now on
+    AllowAlways,
+    /// deny the action, the feedback (maybe empty) is sent back to the model
+    Deny(String),
+}
+
+/// The programs that only read the project, approved with a wildcard by `Rule::suggest`.
+///
+/// Commands of other programs are only suggested as they are, so approving `rm -rf build`
+/// doesn't approve `rm` with any argument. Programs that run code of the project or of a
+/// flag, like `cargo check` with build scripts or `rg --pre`, are not read-only.
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "cat",
+    "cargo metadata",
+    "cargo tree",
+    "diff",
+    "du",
+    "file",
+    "git blame",
+    "git diff",
+    "git log",
+    "git show",
+    "git status",
+    "grep",
+    "head",
+    "ls",
+    "pwd",
+    "stat",
+    "tail",
+    "tree",
+    "wc",
+];
+
+/// The flags making a read-only command write a file or run a program, a wildcard never
+/// approves them.
+const WRITING_FLAGS: &[(&str, &[&str])] = &[
+    ("file", &["-C", "--compile"]),
+    ("git diff", &["--output", "--ext-diff"]),
+    ("git log", &["--output", "--ext-diff"]),
+    ("git show", &["--output", "--ext-diff"]),
+    ("tree", &["-o"]),
+];
+
+/// Returns `true` if a command passes one of the `WRITING_FLAGS` of its program.
+fn has_writing_flags(subject: &str) -> bool {
+    let words: Vec<&str> = subject.split_whitespace().collect();
+    WRITING_FLAGS.iter().any(|(command, flags)| {
+        let command: Vec<&str> = command.split(' ').collect();
+        words.starts_with(&command)
+            && words[command.len()..]
+                .iter()
+                .any(|word| flags.iter().any(|flag| is_flag(word, flag)))
+    })
+}
+
+/// Returns `true` if a word passes a flag: a long flag alone or with an `=value`, a short
+/// one anywhere in a group of short flags like `-ao`.
+fn is_flag(word: &str, flag: &str) -> bool {
+    if flag.starts_with("--") {
+        word.strip_prefix(flag)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+    } else {
+        !word.starts_with("--") && word.starts_with('-') && word.contains(&flag[1..])
+    }
+}
+
+/// The characters chaining, substituting or redirecting shell commands.
+const SHELL_METACHARACTERS: &[char] = &[';', '&', '|', '$', '(', ')', '`', '>', '<', '\n'];
+
+/// Approves the actions of a tool whose subject matches a pattern.
+///
+/// A pattern ending with ` *` matches the text before it, alone or followed by a space and
+/// anything, so `cargo check *` matches `cargo check` and `cargo check --all` but not
+/// `cargo checkout`. A pattern ending with `*` matches any text starting like it, other
+/// patterns match exactly.
+///
+/// Wildcards never match a subject containing shell metacharacters, so `cargo check *`
+/// doesn't approve `cargo check && rm -rf ~`, nor a command with one of its writing flags,
+/// so `git diff *` doesn't approve `git diff --output=src/lib.rs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub tool: String,
+    pub pattern: String,
+}
+
+impl Rule {
+    /// Suggests a pattern for a subject: the read-only command it runs followed by ` *`,
+    /// e.g. `git log *` for `git log -p`, or else the subject itself.
+    pub fn suggest(subject: &str) -> String {
+        if subject.contains(SHELL_METACHARACTERS) || has_writing_flags(subject) {
+            return subject.to_string();
+        }
+        let words: Vec<&str> = subject.split_whitespace().collect();
+        let command = (1..=words.len().min(2))
+            .rev()
+            .map(|count| words[..count].join(" "))
+            .find(|command| READ_ONLY_COMMANDS.contains(&command.as_str()));
+        match command {
+            Some(command) => format!("{} *", command),
+            None => subject.to_string(),
+        }
+    }
+
+    pub fn matches(&self, tool: &str, subject: &str) -> bool {
+        if self.tool != tool {
+            return false;
+        }
+        if subject == self.pattern {
+            return true;
+        }
+        if subject.contains(SHELL_METACHARACTERS) || has_writing_flags(subject) {
+            return false;
+        }
+        if let Some(prefix) = self.pattern.strip_suffix(" *") {
+            subject == prefix
+                || subject
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with(' '))
+        } else if let Some(prefix) = self.pattern.strip_suffix('*') {
+            subject.starts_with(prefix)
+        } else {
+            false
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    /// Formats the rule like a line of the permissions file, `tool pattern`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.tool, self.pattern)
+    }
+}
+
+/// The approvals given by the user.
+///
+/// Session rules are forgotten when the program ends. Rules allowed always are appended
+/// to the permissions file of the project, one `tool pattern` line each, where they can
+/// also be edited by hand. Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct Permissions {
+    session: Vec<Rule>,
+    saved: Vec<Rule>,
+    path: Option<PathBuf>,
+}
+
+impl Permissions {
+    /// Creates a permission set that is not saved anywhere.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Loads the permissions file, a missing file has no rules.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let saved = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (tool, pattern) = line.split_once(' ')?;
+                Some(Rule {
+                    tool: tool.to_string(),
+                    pattern: pattern.trim().to_string(),
+                })
+            })
+            .collect();
+        Ok(Self {
+            session: Vec::new(),
+            saved,
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    /// Returns the permissions file of a project, `<project>/.partner/permissions`.
+    pub fn default_path(project_root: &Path) -> PathBuf {
+        project_root.join(".partner").join("permissions")
+    }
+
+    /// Returns `true` if a session or saved rule approves the action.
+    pub fn is_allowed(&self, tool: &str, subject: &str) -> bool {
+        self.session
+            .iter()
+            .chain(&self.saved)
+            .any(|rule| rule.matches(tool, subject))
+    }
+
+    pub fn allow_session(&mut self, rule: Rule) {
+        self.session.push(rule);
+    }
+
+    /// Adds a rule kept across sessions, appending it to the permissions file.
+    ///
+    /// The rule is in effect for the session even if the file can't be written.
+    pub fn allow_always(&mut self, rule: Rule) -> io::Result<()> {
+        self.saved.push(rule.clone());
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", rule)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the rules saved in the project, in file order.
+    pub fn saved(&self) -> &[Rule] {
+        &self.saved
+    }
+}
@@ -0,0 +1,404 @@
+use crate::command::CommandRegistry;
+use crate::common::{
+    CancellationToken, Choice, Console, ConsoleError, ConsoleInput, FileDiff, Message,
+    Observability, Permission, PermissionRequest, Status, ToolStatus, TurnStatistics,
+};
+use crate::diff::accepted_hunks;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// An expected interaction of a `ScriptedConsole` with the canned reply of the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Interaction {
+    /// a line typed at the prompt, parsed with the command registry like the other consoles
+    Input(String),
+    /// the answer to `if_accept` or `if_yes`
+    Confirm(bool),
+    /// the answer to `ask_user`
+    Answer(String),
+    /// the answer to `request_permission`
+    Permission(Permission),
+    /// the indices chosen by `select`
+    Select(Vec<usize>),
+    /// the accepted flags of `review_diff`, one per hunk, hunks without a flag are rejected
+    Review(Vec<Vec<bool>>),
+    /// the user dismisses the next question and interrupts the turn in flight
+    Cancel,
+}
+
+/// A console call recorded by a `ScriptedConsole`.
+///
+/// Empty tool output fragments, the agent's refresh heartbeats, the durations of the tool
+/// calls and the statistics of the turns are left out to keep the transcript deterministic.
+/// The statistics are kept apart, see `ScriptedConsole::statistics`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Record {
+    /// the line returned by `prompt_input`
+    Input(String),
+    /// the `/help` command, answered by the console before reading the next line
+    Help,
+    /// the `/status` command, answered like `/help`
+    Status,
+    StartResponding,
+    StopResponding,
+    ResponseText(String),
+    ThinkingText(String),
+    ResponseDelta(String),
+    ThinkingDelta(String),
+    Message(Message),
+    ToolStart {
+        name: String,
+        arguments: String,
+    },
+    ToolOutput(String),
+    ToolFinish(ToolStatus),
+    /// the text of `if_accept`
+    Accept(String),
+    /// the text of `if_yes`
+    Confirm(String),
+    /// the text of `ask_user`
+    Question(String),
+    Permission(PermissionRequest),
+    Select {
+        text: String,
+        choices: Vec<Choice>,
+        multiple: bool,
+    },
+    Review(Vec<FileDiff>),
+    /// the question before was dismissed with `Interaction::Cancel`
+    Cancelled,
+}
+
+#[derive(Debug)]
+struct RespondingState {
+    cancellation: CancellationToken,
+    tool_call: bool,
+}
+
+#[derive(Debug)]
+enum State {
+    Prompting,
+    Responding(RespondingState),
+}
+
+/// A `Console` implementation replaying a script of user interactions, for tests.
+///
+/// Every interactive call takes the next `Interaction` of the script and every call is
+/// recorded into a transcript that tests can assert on. Thinking is recorded whatever the
+/// observability flags, which are only kept for the caller. Once the script is over, the
+/// interactive calls fail with `ConsoleError::Terminated`, like a console whose input was
+/// closed.
+///
+/// # Panics
+///
+/// An interactive call panics when the next interaction of the script doesn't answer it,
+/// e.g. `ask_user` while a confirmation is expected, so that a test fails at the call.
+pub struct ScriptedConsole {
+    script: VecDeque<Interaction>,
+    transcript: Vec<Record>,
+    statistics: Vec<TurnStatistics>,
+    state: State,
+    observability: Observability,
+    status: Status,
+    commands: CommandRegistry,
+}
+
+impl ScriptedConsole {
+    /// Creates a new `ScriptedConsole` replaying the given interactions in order.
+    pub fn new(script: impl IntoIterator<Item = Interaction>) -> Self {
+        Self {
+            script: script.into_iter().collect(),
+            transcript: Vec::new(),
+            statistics: Vec::new(),
+            state: State::Prompting,
+            observability: Observability::default(),
+            status: Status::default(),
+            commands: CommandRegistry::default(),
+        }
+    }
+
+    /// Returns the calls recorded so far.
+    pub fn transcript(&self) -> &[Record] {
+        &self.transcript
+    }
+
+    /// Returns the statistics of the turns received so far, whatever the observability flags.
+    pub fn statistics(&self) -> &[TurnStatistics] {
+        &self.statistics
+    }
+
+    /// Returns the response texts and deltas of the transcript joined together, the texts
+    /// are ended by a line break like with `StdIo`.
+    pub fn response(&self) -> String {
+        let mut response = String::new();
+        for record in &self.transcript {
+            match record {
+                Record::ResponseText(text) => {
+                    response.push_str(text);
+                    response.push('\n');
+                }
+                Record::ResponseDelta(delta) => response.push_str(delta),
+                _ => {}
+            }
+        }
+        response
+    }
+
+    /// Returns `true` once every interaction of the script was used.
+    pub fn is_finished(&self) -> bool {
+        self.script.is_empty()
+    }
+
+    /// Records a call while responding.
+    fn record(&mut self, record: Record) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.transcript.push(record);
+        Ok(())
+    }
+
+    /// Records a question and takes the interaction answering it.
+    ///
+    /// `Interaction::Cancel` fires the cancellation token and fails the question.
+    fn ask(&mut self, record: Record) -> Result<Interaction, ConsoleError> {
+        let State::Responding(responding_state) = &self.state else {
+            return Err(ConsoleError::InvalidState);
+        };
+        if responding_state.cancellation.is_cancelled() {
+            return Err(ConsoleError::Cancelled);
+        }
+        let cancellation = responding_state.cancellation.clone();
+        self.transcript.push(record);
+        match self.script.pop_front() {
+            None => Err(ConsoleError::Terminated),
+            Some(Interaction::Cancel) => {
+                self.transcript.push(Record::Cancelled);
+                cancellation.cancel();
+                Err(ConsoleError::Cancelled)
+            }
+            Some(interaction) => Ok(interaction),
+        }
+    }
+}
+
+/// Fails the test at a call that the next interaction of the script doesn't answer.
+fn unexpected(call: &str, interaction: Interaction) -> ! {
+    panic!(
+        "ScriptedConsole: {} doesn't match the next interaction {:?}",
+        call, interaction
+    )
+}
+
+#[async_trait]
+impl Console for ScriptedConsole {
+    /// Takes the next `Interaction::Input` and parses it with the command registry, `/help`
+    /// and `/status` are recorded and answered by taking the next line.
+    async fn prompt_input(&mut self) -> Result<ConsoleInput, ConsoleError> {
+        if !matches!(self.state, State::Prompting) {
+            return Err(ConsoleError::InvalidState);
+        }
+        loop {
+            let line = match self.script.pop_front() {
+                None => return Err(ConsoleError::Terminated),
+                Some(Interaction::Input(line)) => line,
+                Some(interaction) => unexpected("prompt_input", interaction),
+            };
+            self.transcript.push(Record::Input(line.clone()));
+            match self.commands.parse(&line)? {
+                ConsoleInput::Help => self.transcript.push(Record::Help),
+                ConsoleInput::Status => self.transcript.push(Record::Status),
+                input => return Ok(input),
+            }
+        }
+    }
+
+    async fn start_responding(&mut self) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Prompting) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.state = State::Responding(RespondingState {
+            cancellation: CancellationToken::new(),
+            tool_call: false,
+        });
+        self.transcript.push(Record::StartResponding);
+        Ok(())
+    }
+
+    async fn stop_responding(&mut self) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.state = State::Prompting;
+        self.transcript.push(Record::StopResponding);
+        Ok(())
+    }
+
+    /// Returns the token of the current response, it fires when a question is answered
+    /// with `Interaction::Cancel`.
+    fn cancellation_token(&self) -> Option<CancellationToken> {
+        match &self.state {
+            State::Responding(responding_state) => Some(responding_state.cancellation.clone()),
+            State::Prompting => None,
+        }
+    }
+
+    fn commands(&mut self) -> &mut CommandRegistry {
+        &mut self.commands
+    }
+
+    fn observability(&mut self, new_settings: Option<Observability>) -> Observability {
+        let old = self.observability;
+        if let Some(new_val) = new_settings {
+            self.observability = new_val;
+        }
+        old
+    }
+
+    fn status(&mut self) -> &mut Status {
+        &mut self.status
+    }
+
+    async fn add_response_text(&mut self, text: String) -> Result<(), ConsoleError> {
+        self.record(Record::ResponseText(text))
+    }
+
+    async fn add_thinking_text(&mut self, text: String) -> Result<(), ConsoleError> {
+        self.record(Record::ThinkingText(text))
+    }
+
+    async fn add_response_delta(&mut self, delta: String) -> Result<(), ConsoleError> {
+        self.record(Record::ResponseDelta(delta))
+    }
+
+    async fn add_thinking_delta(&mut self, delta: String) -> Result<(), ConsoleError> {
+        self.record(Record::ThinkingDelta(delta))
+    }
+
+    async fn add_message(&mut self, message: Message) -> Result<(), ConsoleError> {
+        self.record(Record::Message(message))
+    }
+
+    async fn start_tool_call(
+        &mut self,
+        name: String,
+        arguments: String,
+    ) -> Result<(), ConsoleError> {
+        let State::Responding(responding_state) = &mut self.state else {
+            return Err(ConsoleError::InvalidState);
+        };
+        if responding_state.tool_call {
+            return Err(ConsoleError::InvalidState);
+        }
+        responding_state.tool_call = true;
+        self.record(Record::ToolStart { name, arguments })
+    }
+
+    async fn add_tool_output(&mut self, output: String) -> Result<(), ConsoleError> {
+        if !matches!(
+            self.state,
+            State::Responding(RespondingState {
+                tool_call: true,
+                ..
+            })
+        ) {
+            return Err(ConsoleError::InvalidState);
+        }
+        if output.is_empty() {
+            return Ok(());
+        }
+        self.record(Record::ToolOutput(output))
+    }
+
+    async fn finish_tool_call(
+        &mut self,
+        status: ToolStatus,
+        _duration: Duration,
+    ) -> Result<(), ConsoleError> {
+        let State::Responding(responding_state) = &mut self.state else {
+            return Err(ConsoleError::InvalidState);
+        };
+        if !responding_state.tool_call {
+            return Err(ConsoleError::InvalidState);
+        }
+        responding_state.tool_call = false;
+        self.record(Record::ToolFinish(status))
+    }
+
+    async fn add_statistics(&mut self, statistics: TurnStatistics) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.statistics.push(statistics);
+        Ok(())
+    }
+
+    async fn if_accept(&mut self, text: String) -> Result<bool, ConsoleError> {
+        match self.ask(Record::Accept(text))? {
+            Interaction::Confirm(answer) => Ok(answer),
+            interaction => unexpected("if_accept", interaction),
+        }
+    }
+
+    /// Takes the next `Interaction::Review`, its flags select the accepted hunks.
+    async fn review_diff(&mut self, files: Vec<FileDiff>) -> Result<Vec<FileDiff>, ConsoleError> {
+        match self.ask(Record::Review(files.clone()))? {
+            Interaction::Review(accepted) => Ok(accepted_hunks(files, &accepted)),
+            interaction => unexpected("review_diff", interaction),
+        }
+    }
+
+    async fn request_permission(
+        &mut self,
+        request: PermissionRequest,
+    ) -> Result<Permission, ConsoleError> {
+        match self.ask(Record::Permission(request))? {
+            Interaction::Permission(permission) => Ok(permission),
+            interaction => unexpected("request_permission", interaction),
+        }
+    }
+
+    /// Takes the next `Interaction::Select`, the indices are sorted and must be valid.
+    async fn select(
+        &mut self,
+        text: String,
+        choices: Vec<Choice>,
+        multiple: bool,
+    ) -> Result<Vec<usize>, ConsoleError> {
+        if choices.is_empty() {
+            return Err(ConsoleError::InvalidState);
+        }
+        let count = choices.len();
+        let record = Record::Select {
+            text,
+            choices,
+            multiple,
+        };
+        match self.ask(record)? {
+            Interaction::Select(mut indices)
+                if indices.iter().all(|index| *index < count)
+                    && (multiple || indices.len() == 1) =>
+            {
+                indices.sort_unstable();
+                indices.dedup();
+                Ok(indices)
+            }
+            interaction => unexpected("select", interaction),
+        }
+    }
+
+    async fn if_yes(&mut self, text: String) -> Result<bool, ConsoleError> {
+        match self.ask(Record::Confirm(text))? {
+            Interaction::Confirm(answer) => Ok(answer),
+            interaction => unexpected("if_yes", interaction),
+        }
+    }
+
+    async fn ask_user(&mut self, text: String) -> Result<String, ConsoleError> {
+        match self.ask(Record::Question(text))? {
+            Interaction::Answer(answer) => Ok(answer),
+            interaction => unexpected("ask_user", interaction),
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use serde::Serialize;
+
+/// An option of a selection prompt, see `Console::select`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Choice {
+    pub label: String,
+    /// an explanation shown with the label
+    pub description: Option<String>,
+}
+
+impl Choice {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            description: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::time::Duration;
+
+/// A tool call of a turn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolCallStatistics {
+    pub name: String,
+    #[serde(rename = "duration_ms", serialize_with = "as_millis")]
+    pub duration: Duration,
+    pub success: bool,
+}
+
+/// The statistics of one agent turn, see `Console::add_statistics`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TurnStatistics {
+    pub input_tokens: u64,
+    /// the part of the input tokens read from the cache of the provider
+    pub cached_tokens: u64,
+    pub output_tokens: u64,
+    pub model_calls: usize,
+    pub tool_calls: Vec<ToolCallStatistics>,
+    /// the time from the start of the turn to the first streamed token
+    #[serde(
+        rename = "time_to_first_token_ms",
+        serialize_with = "as_optional_millis"
+    )]
+    pub time_to_first_token: Option<Duration>,
+    #[serde(rename = "duration_ms", serialize_with = "as_millis")]
+    pub duration: Duration,
+    /// the estimated cost in US dollars, if the prices of the model are known
+    pub cost: Option<f64>,
+}
+
+/// The totals of the turns of a session, printed by `/cost`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionStatistics {
+    pub turns: usize,
+    pub input_tokens: u64,
+    pub cached_tokens: u64,
+    pub output_tokens: u64,
+    pub model_calls: usize,
+    pub tool_calls: usize,
+    /// the time spent in tool calls
+    pub tool_time: Duration,
+    pub duration: Duration,
+    /// the estimated cost in US dollars, `None` until a turn with known prices
+    pub cost: Option<f64>,
+}
+
+impl SessionStatistics {
+    /// Adds a turn to the totals.
+    pub fn add_turn(&mut self, turn: &TurnStatistics) {
+        self.turns += 1;
+        self.input_tokens += turn.input_tokens;
+        self.cached_tokens += turn.cached_tokens;
+        self.output_tokens += turn.output_tokens;
+        self.model_calls += turn.model_calls;
+        self.tool_calls += turn.tool_calls.len();
+        self.tool_time += turn
+            .tool_calls
+            .iter()
+            .map(|tool_call| tool_call.duration)
+            .sum::<Duration>();
+        self.duration += turn.duration;
+        if let Some(cost) = turn.cost {
+            *self.cost.get_or_insert(0.0) += cost;
+        }
+    }
+}
+
+/// Writes the token counts, e.g. `Tokens: 1200 in (300 cached), 250 out`.
+fn write_tokens(f: &mut fmt::Formatter<'_>, input: u64, cached: u64, output: u64) -> fmt::Result {
+    write!(f, "Tokens: {} in", input)?;
+    if cached > 0 {
+        write!(f, " ({} cached)", cached)?;
+    }
+    writeln!(f, ", {} out", output)
+}
+
+/// Renders the report shown at the end of a turn, one line per topic.
+impl fmt::Display for TurnStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_tokens(f, self.input_tokens, self.cached_tokens, self.output_tokens)?;
+        write!(f, "Model calls: {}", self.model_calls)?;
+        if let Some(time_to_first_token) = self.time_to_first_token {
+            write!(
+                f,
+                ", first token after {:.1}s",
+                time_to_first_token.as_secs_f64()
+            )?;
+        }
+        writeln!(f)?;
+        if !self.tool_calls.is_empty() {
+            let tool_calls: Vec<String> = self
+                .tool_calls
+                .iter()
+                .map(|tool_call| {
+                    format!(
+                        "{} {:.1}s{}",
+                        tool_call.name,
+                        tool_call.duration.as_secs_f64(),
+                        if tool_call.success { "" } else { " (failed)" }
+                    )
+                })
+                .collect();
+            writeln!(f, "Tool calls: {}", tool_calls.join(", "))?;
+        }
+        write!(f, "Response time: {:.1}s", self.duration.as_secs_f64())?;
+        if let Some(cost) = self.cost {
+            write!(f, "\nCost: ${:.4}", cost)?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders the session totals, one line per topic.
+impl fmt::Display for SessionStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Session: {} turns, {} model calls, {} tool calls ({:.1}s)",
+            self.turns,
+            self.model_calls,
+            self.tool_calls,
+            self.tool_time.as_secs_f64()
+        )?;
+        write_tokens(f, self.input_tokens, self.cached_tokens, self.output_tokens)?;
+        write!(f, "Response time: {:.1}s", self.duration.as_secs_f64())?;
+        match self.cost {
+            Some(cost) => write!(f, "\nCost: ${:.4}", cost),
+            None => write!(f, "\nCost: unknown, the prices of the model are not known"),
+        }
+    }
+}
+
+fn as_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u128(duration.as_millis())
+}
+
+fn as_optional_millis<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serializer.serialize_some(&duration.as_millis()),
+        None => serializer.serialize_none(),
+    }
+}
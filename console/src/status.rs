@@ -0,0 +1,96 @@
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+/// What the console is doing, shown at the end of the status line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsoleMode {
+    /// waiting for the next prompt
+    Prompting,
+    /// showing the response of the agent
+    Responding,
+    /// waiting for the user to answer a question of the agent, e.g. a permission request
+    AwaitingApproval,
+}
+
+impl fmt::Display for ConsoleMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConsoleMode::Prompting => "prompting",
+            ConsoleMode::Responding => "responding",
+            ConsoleMode::AwaitingApproval => "awaiting approval",
+        })
+    }
+}
+
+/// The session status shown by the consoles, see `Console::status`.
+///
+/// The agent fills in the model, the context usage and the cost, the caller fills in the
+/// workspace with `refresh_workspace`. Unknown values are left out of the status line.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Status {
+    pub model: Option<String>,
+    /// the share of the context window taken by the conversation, in percent
+    pub context_usage: Option<u8>,
+    /// the cost of the session so far, in US dollars
+    pub cost: Option<f64>,
+    pub working_dir: Option<String>,
+    pub git_branch: Option<String>,
+    /// whether the git working tree has uncommitted changes
+    pub git_dirty: bool,
+}
+
+impl Status {
+    /// Sets the working directory, with the home directory shortened to `~`, and reads its
+    /// git branch and whether the working tree is dirty.
+    ///
+    /// The branch is `None` outside of a git repository or when git is missing.
+    pub fn refresh_workspace(&mut self, dir: &Path) {
+        let home = std::env::var_os("HOME");
+        let shortened = home
+            .as_ref()
+            .and_then(|home| dir.strip_prefix(home).ok())
+            .map(|relative| Path::new("~").join(relative));
+        self.working_dir = Some(shortened.as_deref().unwrap_or(dir).display().to_string());
+        self.git_branch = git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])
+            .map(|branch| branch.trim().to_string());
+        self.git_dirty = self.git_branch.is_some()
+            && git(dir, &["status", "--porcelain"]).is_some_and(|changes| !changes.is_empty());
+    }
+
+    /// Returns the status line, e.g. `gpt-4o | context 42% | $0.0123 | ~/project | main* |
+    /// prompting`.
+    pub fn line(&self, mode: ConsoleMode) -> String {
+        let mut parts = Vec::new();
+        parts.extend(self.model.clone());
+        parts.extend(
+            self.context_usage
+                .map(|usage| format!("context {}%", usage)),
+        );
+        parts.extend(self.cost.map(|cost| format!("${:.4}", cost)));
+        parts.extend(self.working_dir.clone());
+        parts.extend(
+            self.git_branch
+                .as_ref()
+                .map(|branch| format!("{}{}", branch, if self.git_dirty { "*" } else { "" })),
+        );
+        parts.push(mode.to_string());
+        parts.join(" | ")
+    }
+}
+
+/// Runs a git command in `dir` and returns its output, `None` if it fails.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
@@ -1,11 +1,80 @@
-use crate::common::{Console, ConsoleError, ConsoleInput, Observability};
+use crate::command::CommandRegistry;
+use crate::common::{
+    CancellationToken, Choice, Console, ConsoleError, ConsoleInput, ConsoleMode, FileDiff,
+    Message, Observability, Permission, PermissionRequest, Status, ToolStatus, TurnStatistics,
+};
+use crate::diff::accepted_hunks;
+use crate::history::History;
+use crate::message::shorten;
 use async_trait::async_trait;
-use std::io::{BufRead, BufReader, Read, Write};
-use std::time::Instant;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+use std::io::{IsTerminal as _, Write as _};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt as _, AsyncRead, AsyncWrite, AsyncWriteExt as _, BufReader};
+
+/// The kind of streamed text whose line is still open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenLine {
+    Response,
+    Thinking,
+}
 
 #[derive(Debug)]
 struct RespondingState {
     start_time: Option<Instant>,
+    open_line: Option<OpenLine>,
+    cancellation: CancellationToken,
+    /// the name of the running tool call
+    tool_call: Option<String>,
+    /// whether the statistics of the turn were printed, they replace the response time
+    reported: bool,
+}
+
+/// The answers of a hunk review, like `git add -p`.
+const REVIEW_HELP: &str = "y - accept this hunk
+n - reject this hunk
+a - accept this hunk and all later hunks in the file
+d - reject this hunk and all later hunks in the file
+q - reject this hunk and all remaining ones
+? - print help";
+
+/// Opens and closes a multi-line prompt block.
+const BLOCK_DELIMITER: &str = "\"\"\"";
+
+/// The prompt of the line editor for the continuation lines of a multi-line prompt.
+const CONTINUATION_PROMPT: &str = "... ";
+
+/// The arguments of a tool call are cut to this many characters in its start line.
+const MAX_ARGUMENTS_CHARS: usize = 80;
+
+/// Routes Ctrl-C to the turn in flight instead of killing the process.
+///
+/// The signal listener is installed on the first response, after that Ctrl-C in the
+/// prompting state exits the process with the conventional status 130.
+#[derive(Debug, Default)]
+struct CtrlC {
+    current: Arc<Mutex<Option<CancellationToken>>>,
+    listening: bool,
+}
+
+impl CtrlC {
+    fn set(&mut self, token: Option<CancellationToken>) {
+        *self.current.lock().unwrap() = token;
+        if !self.listening && tokio::runtime::Handle::try_current().is_ok() {
+            self.listening = true;
+            let current = self.current.clone();
+            tokio::spawn(async move {
+                while tokio::signal::ctrl_c().await.is_ok() {
+                    match current.lock().unwrap().as_ref() {
+                        Some(token) => token.cancel(),
+                        None => std::process::exit(130),
+                    }
+                }
+            });
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -16,75 +85,323 @@ enum State {
 
 /// A `Console` implementation that uses standard I/O streams.
 ///
-/// This struct provides a basic, text-based console interface by reading from a `Read`
-/// buffer (like `stdin`) and writing to a `Write` buffer (like `stdout`). It's suitable
-/// for command-line applications and testing purposes.
+/// This struct provides a basic, text-based console interface by reading from an
+/// `AsyncRead` stream (like `stdin`) and writing to an `AsyncWrite` stream (like `stdout`).
+/// It's suitable for command-line applications and testing purposes.
 pub struct StdIo<R, W>
 where
-    R: Read,
-    W: Write,
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
 {
-    reader: BufReader<R>,
+    /// the input, `None` for a console without input
+    reader: Option<BufReader<R>>,
     writer: W,
+    /// the text written since the last flush
+    output: Vec<u8>,
     state: State,
     observability: Observability,
+    status: Status,
+    commands: CommandRegistry,
+    ctrl_c: Option<CtrlC>,
+    history: History,
+    line_editor: Option<DefaultEditor>,
 }
 
-impl StdIo<std::io::Stdin, std::io::Stdout> {
+impl StdIo<tokio::io::Stdin, tokio::io::Stdout> {
     /// Creates a new `StdIo` instance that reads from `stdin` and writes to `stdout`.
+    ///
+    /// Ctrl-C pressed while responding cancels the turn instead of killing the process.
+    /// When `stdin` is a terminal, lines are read with a readline-style editor offering
+    /// line editing, Up/Down history navigation and Ctrl-R search.
     pub fn new() -> Self {
+        let line_editor = if std::io::stdin().is_terminal() {
+            DefaultEditor::new().ok()
+        } else {
+            None
+        };
         Self {
-            reader: BufReader::new(std::io::stdin()),
-            writer: std::io::stdout(),
+            reader: Some(BufReader::new(tokio::io::stdin())),
+            writer: tokio::io::stdout(),
+            output: Vec::new(),
             state: State::Prompting,
             observability: Observability::default(),
+            status: Status::default(),
+            commands: CommandRegistry::default(),
+            ctrl_c: Some(CtrlC::default()),
+            history: History::in_memory(),
+            line_editor,
+        }
+    }
+}
+
+impl Default for StdIo<tokio::io::Stdin, tokio::io::Stdout> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: AsyncWrite + Unpin> StdIo<tokio::io::Empty, W> {
+    /// Creates a `StdIo` instance without input, reading fails with
+    /// `ConsoleError::NoInput`.
+    pub fn write_only(writer: W) -> Self {
+        Self {
+            reader: None,
+            ..Self::new_with_buffers(tokio::io::empty(), writer)
         }
     }
 }
 
-impl<R: Read, W: Write> StdIo<R, W> {
+impl<R: AsyncRead + Unpin, W: AsyncWrite + Unpin> StdIo<R, W> {
     /// Creates a new `StdIo` instance with the given reader and writer buffers.
     ///
     /// This is useful for testing, allowing you to replace `stdin` and `stdout` with
     /// in-memory buffers.
     pub fn new_with_buffers(reader: R, writer: W) -> Self {
         Self {
-            reader: BufReader::new(reader),
+            reader: Some(BufReader::new(reader)),
             writer,
+            output: Vec::new(),
             state: State::Prompting,
             observability: Observability::default(),
+            status: Status::default(),
+            commands: CommandRegistry::default(),
+            ctrl_c: None,
+            history: History::in_memory(),
+            line_editor: None,
+        }
+    }
+
+    /// Replaces the prompt history, e.g. with the history file of the project.
+    pub fn with_history(mut self, history: History) -> Self {
+        if let Some(line_editor) = &mut self.line_editor {
+            let _ = line_editor.clear_history();
+            for entry in history.entries() {
+                let _ = line_editor.add_history_entry(entry.as_str());
+            }
+        }
+        self.history = history;
+        self
+    }
+
+    /// Returns the prompt history.
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Writes out the text buffered since the last flush.
+    async fn flush(&mut self) -> Result<(), ConsoleError> {
+        self.writer
+            .write_all(&self.output)
+            .await
+            .map_err(|_| ConsoleError::Terminated)?;
+        self.output.clear();
+        self.writer
+            .flush()
+            .await
+            .map_err(|_| ConsoleError::Terminated)
+    }
+
+    /// Reads a line with the line editor if there is one, otherwise from the reader.
+    ///
+    /// The line is returned without its line break, `None` means the end of the input.
+    /// Ctrl-C in the line editor fails with `ConsoleError::Cancelled`, a console without
+    /// input fails with `ConsoleError::NoInput`. The line editor blocks, so it runs on a
+    /// blocking thread of the runtime.
+    async fn read_line(&mut self, prompt: &str) -> Result<Option<String>, ConsoleError> {
+        self.flush().await?;
+        if let Some(mut line_editor) = self.line_editor.take() {
+            let prompt = prompt.to_string();
+            let (line_editor, line) = tokio::task::spawn_blocking(move || {
+                let line = line_editor.readline(&prompt);
+                (line_editor, line)
+            })
+            .await
+            .map_err(|_| ConsoleError::Terminated)?;
+            self.line_editor = Some(line_editor);
+            return match line {
+                Ok(line) => Ok(Some(line)),
+                Err(ReadlineError::Eof) => Ok(None),
+                Err(ReadlineError::Interrupted) => Err(ConsoleError::Cancelled),
+                Err(_) => Err(ConsoleError::Terminated),
+            };
+        }
+        let Some(reader) = &mut self.reader else {
+            return Err(ConsoleError::NoInput);
+        };
+        let mut buffer = String::new();
+        match reader.read_line(&mut buffer).await {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                buffer.truncate(buffer.trim_end_matches(['\r', '\n']).len());
+                Ok(Some(buffer))
+            }
+            Err(_) => Err(ConsoleError::Terminated),
+        }
+    }
+
+    /// Reads a prompt of one or more lines.
+    ///
+    /// A line ending with `\` continues on the next line, and a line starting with `"""`
+    /// opens a block running up to the next line ending with `"""`. Returns `None` at the
+    /// end of the input, a prompt cut short by it is returned as it is.
+    ///
+    /// The prompt is trimmed, except for a block: only its delimiter lines are left out
+    /// when they hold nothing else, so the indentation of pasted code is kept.
+    async fn read_prompt(&mut self) -> Result<Option<String>, ConsoleError> {
+        let Some(mut line) = self.read_line("> ").await? else {
+            return Ok(None);
+        };
+        let mut lines = Vec::new();
+        if let Some(rest) = line.trim_start().strip_prefix(BLOCK_DELIMITER) {
+            line = rest.to_string();
+            let mut first = true;
+            loop {
+                if let Some(last) = line.trim_end().strip_suffix(BLOCK_DELIMITER) {
+                    if !last.trim().is_empty() {
+                        lines.push(last.to_string());
+                    }
+                    break;
+                }
+                if !first || !line.trim().is_empty() {
+                    lines.push(line);
+                }
+                first = false;
+                match self.read_line(CONTINUATION_PROMPT).await? {
+                    Some(next) => line = next,
+                    None => break,
+                }
+            }
+            return Ok(Some(lines.join("\n")));
+        }
+        while let Some(start) = line.strip_suffix('\\') {
+            lines.push(start.to_string());
+            match self.read_line(CONTINUATION_PROMPT).await? {
+                Some(next) => line = next,
+                None => return Ok(Some(lines.join("\n").trim().to_string())),
+            }
+        }
+        lines.push(line);
+        Ok(Some(lines.join("\n").trim().to_string()))
+    }
+
+    /// Reads the answer to a question, failing with `ConsoleError::Terminated` at the end
+    /// of the input.
+    ///
+    /// The answer is read like the prompts, with the line editor if there is one, so a
+    /// pending read never outlives the question. The last line of the question becomes
+    /// the prompt of the line editor, which redraws it. Ctrl-C in the line editor cancels
+    /// the turn.
+    async fn read_answer(&mut self) -> Result<String, ConsoleError> {
+        let mut prompt = String::new();
+        if self.line_editor.is_some() {
+            let start = self
+                .output
+                .iter()
+                .rposition(|byte| *byte == b'\n')
+                .map_or(0, |index| index + 1);
+            prompt = String::from_utf8_lossy(&self.output[start..]).into_owned();
+            self.output.truncate(start);
+        }
+        match self.read_line(&prompt).await {
+            Ok(Some(line)) => Ok(line),
+            Ok(None) => Err(ConsoleError::Terminated),
+            Err(ConsoleError::Cancelled) => {
+                if let State::Responding(responding_state) = &self.state {
+                    responding_state.cancellation.cancel();
+                }
+                Err(ConsoleError::Cancelled)
+            }
+            Err(err) => Err(err),
         }
     }
+
+    /// Fails with `ConsoleError::Cancelled` if the turn in flight was interrupted.
+    fn check_cancelled(&self) -> Result<(), ConsoleError> {
+        match &self.state {
+            State::Responding(responding_state) if responding_state.cancellation.is_cancelled() => {
+                Err(ConsoleError::Cancelled)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Terminates the line left open by streamed fragments, if any.
+    fn close_line(&mut self) -> Result<(), ConsoleError> {
+        if let State::Responding(responding_state) = &mut self.state {
+            if responding_state.open_line.take().is_some() {
+                writeln!(self.output).map_err(|_| ConsoleError::Terminated)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a streamed fragment, starting a new line if another kind of text was streaming.
+    async fn write_delta(&mut self, kind: OpenLine, delta: &str) -> Result<(), ConsoleError> {
+        let State::Responding(responding_state) = &mut self.state else {
+            return Err(ConsoleError::InvalidState);
+        };
+        if responding_state.open_line != Some(kind) {
+            if responding_state.open_line.is_some() {
+                writeln!(self.output).map_err(|_| ConsoleError::Terminated)?;
+            }
+            responding_state.open_line = Some(kind);
+        }
+        write!(self.output, "{}", delta).map_err(|_| ConsoleError::Terminated)?;
+        self.flush().await
+    }
 }
 
 #[async_trait]
 /// Implements the `Console` trait for the `StdIo` struct.
 ///
-/// This implementation provides a straightforward, synchronous console experience. It's
-/// important to note that while the trait methods are `async`, this implementation
-/// blocks on I/O operations.
+/// This implementation provides a straightforward line-based console experience. The text
+/// is formatted into a buffer and written out asynchronously, and waiting for the user
+/// doesn't block the runtime, so background tasks keep running meanwhile.
 impl<R, W> Console for StdIo<R, W>
 where
-    R: Read + Send,
-    W: Write + Send,
+    R: AsyncRead + Unpin + Send,
+    W: AsyncWrite + Unpin + Send,
 {
     /// Prompts for user input by reading a line from the input buffer.
     ///
     /// The prompt itself is not displayed by this method; it's assumed to be handled
-    /// by the calling context or the terminal's natural behavior.
+    /// by the calling context or the terminal's natural behavior, only the line editor
+    /// shows `> `. Blank lines are skipped and prompts may span several lines, see
+    /// `read_prompt`. `/help` and `/status` are answered by printing the registered commands
+    /// or the status line and reading the next prompt. Every prompt read is added to the history.
     async fn prompt_input(&mut self) -> Result<ConsoleInput, ConsoleError> {
         if !matches!(self.state, State::Prompting) {
             return Err(ConsoleError::InvalidState);
         }
-        let mut buffer = String::new();
-        if self.reader.read_line(&mut buffer).is_ok() {
-            let line = buffer.trim();
-            if line.is_empty() {
-                return Err(ConsoleError::Terminated);
+        loop {
+            let line = match self.read_prompt().await {
+                Ok(Some(line)) => line,
+                // Ctrl-C at the prompt ends the session, like the end of the input
+                Ok(None) | Err(ConsoleError::Cancelled) => return Err(ConsoleError::Terminated),
+                Err(err) => return Err(err),
+            };
+            let line = line.as_str();
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(line_editor) = &mut self.line_editor {
+                let _ = line_editor.add_history_entry(line);
+            }
+            // a history that can't be saved must not end the session
+            let _ = self.history.add(line);
+            match self.commands.parse(line)? {
+                ConsoleInput::Help => {
+                    writeln!(self.output, "{}", self.commands.help())
+                        .map_err(|_| ConsoleError::Terminated)?;
+                    self.flush().await?;
+                }
+                ConsoleInput::Status => {
+                    writeln!(self.output, "{}", self.status.line(ConsoleMode::Prompting))
+                        .map_err(|_| ConsoleError::Terminated)?;
+                    self.flush().await?;
+                }
+                input => return Ok(input),
             }
-            ConsoleInput::from_line(line)
-        } else {
-            Err(ConsoleError::Terminated)
         }
     }
 
@@ -96,38 +413,62 @@ where
         if !matches!(self.state, State::Prompting) {
             return Err(ConsoleError::InvalidState);
         }
+        let cancellation = CancellationToken::new();
         self.state = State::Responding(RespondingState {
             start_time: if self.observability.statistics {
                 Some(Instant::now())
             } else {
                 None
             },
+            open_line: None,
+            cancellation: cancellation.clone(),
+            tool_call: None,
+            reported: false,
         });
+        if let Some(ctrl_c) = &mut self.ctrl_c {
+            ctrl_c.set(Some(cancellation));
+        }
         Ok(())
     }
 
     /// Switches the internal state back to `Prompting`.
     ///
-    /// If statistics are enabled, it calculates and prints the total response time.
+    /// If statistics are enabled and the agent sent no report, it calculates and prints the
+    /// total response time.
     async fn stop_responding(&mut self) -> Result<(), ConsoleError> {
+        self.close_line()?;
+        if let Some(ctrl_c) = &mut self.ctrl_c {
+            ctrl_c.set(None);
+        }
         let state = std::mem::replace(&mut self.state, State::Prompting);
         if let State::Responding(responding_state) = state {
-            if self.observability.statistics {
+            if self.observability.statistics && !responding_state.reported {
                 if let Some(start_time) = responding_state.start_time {
                     let duration = start_time.elapsed();
-                    writeln!(self.writer, "Response time: {:?}", duration)
+                    writeln!(self.output, "Response time: {:?}", duration)
                         .map_err(|_| ConsoleError::Terminated)?;
-                    writeln!(self.writer).map_err(|_| ConsoleError::Terminated)?;
-                    self.writer.flush().map_err(|_| ConsoleError::Terminated)?;
+                    writeln!(self.output).map_err(|_| ConsoleError::Terminated)?;
                 }
             }
-            Ok(())
+            self.flush().await
         } else {
             self.state = state; // Restore state if it wasn't Responding
             Err(ConsoleError::InvalidState)
         }
     }
 
+    /// Returns the token of the current response, cancelled by Ctrl-C for the `stdin` console.
+    fn cancellation_token(&self) -> Option<CancellationToken> {
+        match &self.state {
+            State::Responding(responding_state) => Some(responding_state.cancellation.clone()),
+            State::Prompting => None,
+        }
+    }
+
+    fn commands(&mut self) -> &mut CommandRegistry {
+        &mut self.commands
+    }
+
     /// Gets or sets the observability flags.
     ///
     /// See `Console::observability` for detailed behavior.
@@ -139,6 +480,10 @@ where
         old
     }
 
+    fn status(&mut self) -> &mut Status {
+        &mut self.status
+    }
+
     /// Appends a block of text to the agent's response area.
     ///
     /// This implementation writes the text followed by a newline to the output buffer.
@@ -146,11 +491,91 @@ where
         if !matches!(self.state, State::Responding(_)) {
             return Err(ConsoleError::InvalidState);
         }
-        writeln!(self.writer, "{}", text).map_err(|_| ConsoleError::Terminated)?;
-        self.writer.flush().map_err(|_| ConsoleError::Terminated)?;
+        self.close_line()?;
+        writeln!(self.output, "{}", text).map_err(|_| ConsoleError::Terminated)?;
+        self.flush().await?;
         Ok(())
     }
 
+    /// Writes the plain text form of a message, like `add_response_text`.
+    async fn add_message(&mut self, message: Message) -> Result<(), ConsoleError> {
+        self.add_response_text(message.to_string()).await
+    }
+
+    /// Writes a compact `Action: name(arguments)` line, long arguments are shortened.
+    async fn start_tool_call(
+        &mut self,
+        name: String,
+        arguments: String,
+    ) -> Result<(), ConsoleError> {
+        let State::Responding(responding_state) = &mut self.state else {
+            return Err(ConsoleError::InvalidState);
+        };
+        if responding_state.tool_call.is_some() {
+            return Err(ConsoleError::InvalidState);
+        }
+        let line = format!(
+            "Action: {}({})",
+            name,
+            shorten(&arguments, MAX_ARGUMENTS_CHARS)
+        );
+        responding_state.tool_call = Some(name);
+        self.add_response_text(line).await
+    }
+
+    /// The output of tools is not printed, only the start and finish lines are.
+    async fn add_tool_output(&mut self, _output: String) -> Result<(), ConsoleError> {
+        match &self.state {
+            State::Responding(RespondingState {
+                tool_call: Some(_), ..
+            }) => Ok(()),
+            _ => Err(ConsoleError::InvalidState),
+        }
+    }
+
+    /// Writes a compact line with the status and the duration of the tool call.
+    async fn finish_tool_call(
+        &mut self,
+        status: ToolStatus,
+        duration: Duration,
+    ) -> Result<(), ConsoleError> {
+        let State::Responding(responding_state) = &mut self.state else {
+            return Err(ConsoleError::InvalidState);
+        };
+        let Some(name) = responding_state.tool_call.take() else {
+            return Err(ConsoleError::InvalidState);
+        };
+        let seconds = duration.as_secs_f64();
+        let line = match status {
+            ToolStatus::Succeeded => format!("Finished {} in {:.1}s", name, seconds),
+            ToolStatus::Failed(error) => format!(
+                "Failed {} after {:.1}s: {}",
+                name,
+                seconds,
+                shorten(&error, MAX_ARGUMENTS_CHARS)
+            ),
+        };
+        self.add_response_text(line).await
+    }
+
+    /// Prints the statistics of the turn followed by an empty line, if statistics are
+    /// enabled.
+    async fn add_statistics(&mut self, statistics: TurnStatistics) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        if !self.observability.statistics {
+            return Ok(());
+        }
+        self.close_line()?;
+        writeln!(self.output, "{}", statistics).map_err(|_| ConsoleError::Terminated)?;
+        writeln!(self.output).map_err(|_| ConsoleError::Terminated)?;
+        if let State::Responding(responding_state) = &mut self.state {
+            responding_state.reported = true;
+        }
+        self.flush().await
+    }
+
     /// Appends text to the agent's "thinking" status display if enabled.
     ///
     /// This writes the text to the output buffer only if the `thinking` flag in
@@ -160,8 +585,27 @@ where
             return Err(ConsoleError::InvalidState);
         }
         if self.observability.thinking {
-            writeln!(self.writer, "{}", text).map_err(|_| ConsoleError::Terminated)?;
-            self.writer.flush().map_err(|_| ConsoleError::Terminated)?;
+            self.close_line()?;
+            writeln!(self.output, "{}", text).map_err(|_| ConsoleError::Terminated)?;
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Writes a streamed fragment of the response as is, without a line break.
+    ///
+    /// The line is terminated when a block of text is written or the response ends.
+    async fn add_response_delta(&mut self, delta: String) -> Result<(), ConsoleError> {
+        self.write_delta(OpenLine::Response, &delta).await
+    }
+
+    /// Writes a streamed fragment of the thinking text if thinking is enabled.
+    async fn add_thinking_delta(&mut self, delta: String) -> Result<(), ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        if self.observability.thinking {
+            self.write_delta(OpenLine::Thinking, &delta).await?;
         }
         Ok(())
     }
@@ -174,18 +618,15 @@ where
         if !matches!(self.state, State::Responding(_)) {
             return Err(ConsoleError::InvalidState);
         }
+        self.check_cancelled()?;
+        self.close_line()?;
         loop {
-            writeln!(self.writer, "{} [Y/n]", text).map_err(|_| ConsoleError::Terminated)?;
-            self.writer.flush().map_err(|_| ConsoleError::Terminated)?;
-            let mut buffer = String::new();
-            if self.reader.read_line(&mut buffer).is_ok() {
-                match buffer.trim().to_lowercase().as_str() {
-                    "y" | "yes" | "" => return Ok(true),
-                    "n" | "no" => return Ok(false),
-                    _ => continue,
-                }
-            } else {
-                return Err(ConsoleError::Terminated);
+            writeln!(self.output, "{} [Y/n]", text).map_err(|_| ConsoleError::Terminated)?;
+            let buffer = self.read_answer().await?;
+            match buffer.trim().to_lowercase().as_str() {
+                "y" | "yes" | "" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => continue,
             }
         }
     }
@@ -198,18 +639,165 @@ where
         if !matches!(self.state, State::Responding(_)) {
             return Err(ConsoleError::InvalidState);
         }
+        self.check_cancelled()?;
+        self.close_line()?;
         loop {
-            writeln!(self.writer, "{} [y/N]", text).map_err(|_| ConsoleError::Terminated)?;
-            self.writer.flush().map_err(|_| ConsoleError::Terminated)?;
-            let mut buffer = String::new();
-            if self.reader.read_line(&mut buffer).is_ok() {
+            writeln!(self.output, "{} [y/N]", text).map_err(|_| ConsoleError::Terminated)?;
+            let buffer = self.read_answer().await?;
+            match buffer.trim().to_lowercase().as_str() {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" | "" => return Ok(false),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Reviews the changes like `git add -p`: each hunk is printed and the user answers
+    /// with one of `y,n,a,d,q`, `?` prints the help.
+    async fn review_diff(&mut self, files: Vec<FileDiff>) -> Result<Vec<FileDiff>, ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.close_line()?;
+        let mut accepted: Vec<Vec<bool>> = files
+            .iter()
+            .map(|file| vec![false; file.hunks.len()])
+            .collect();
+        let mut quit = false;
+        for (file, accepted) in files.iter().zip(&mut accepted) {
+            if quit {
+                break;
+            }
+            writeln!(self.output, "--- a/{}\n+++ b/{}", file.path, file.path)
+                .map_err(|_| ConsoleError::Terminated)?;
+            let mut index = 0;
+            while index < file.hunks.len() {
+                self.check_cancelled()?;
+                let hunk = &file.hunks[index];
+                writeln!(self.output, "{}", hunk.header).map_err(|_| ConsoleError::Terminated)?;
+                for line in &hunk.lines {
+                    writeln!(self.output, "{}", line).map_err(|_| ConsoleError::Terminated)?;
+                }
+                write!(
+                    self.output,
+                    "({}/{}) Accept this hunk [y,n,a,d,q,?]? ",
+                    index + 1,
+                    file.hunks.len()
+                )
+                .map_err(|_| ConsoleError::Terminated)?;
+                let buffer = self.read_answer().await?;
                 match buffer.trim().to_lowercase().as_str() {
-                    "y" | "yes" => return Ok(true),
-                    "n" | "no" | "" => return Ok(false),
-                    _ => continue,
+                    "y" => accepted[index] = true,
+                    "n" => {}
+                    "a" => {
+                        accepted[index..].fill(true);
+                        break;
+                    }
+                    "d" => break,
+                    "q" => {
+                        quit = true;
+                        break;
+                    }
+                    _ => {
+                        writeln!(self.output, "{}", REVIEW_HELP)
+                            .map_err(|_| ConsoleError::Terminated)?;
+                        continue;
+                    }
                 }
+                index += 1;
+            }
+        }
+        self.flush().await?;
+        Ok(accepted_hunks(files, &accepted))
+    }
+
+    /// Asks for a permission on one line: `y` allows once, `s` for the session, `a` always,
+    /// `n` or an empty line denies and any other answer denies with it as the feedback.
+    async fn request_permission(
+        &mut self,
+        request: PermissionRequest,
+    ) -> Result<Permission, ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.check_cancelled()?;
+        self.close_line()?;
+        writeln!(self.output, "Allow {}: {}?", request.tool, request.subject)
+            .map_err(|_| ConsoleError::Terminated)?;
+        write!(
+            self.output,
+            "[y]es once, [s]ession or [a]lways for `{}`, [n]o or feedback to deny: ",
+            request.pattern
+        )
+        .map_err(|_| ConsoleError::Terminated)?;
+        let buffer = self.read_answer().await?;
+        let answer = buffer.trim();
+        Ok(match answer.to_lowercase().as_str() {
+            "y" | "yes" => Permission::AllowOnce,
+            "s" | "session" => Permission::AllowSession,
+            "a" | "always" => Permission::AllowAlways,
+            "n" | "no" | "" => Permission::Deny(String::new()),
+            _ => Permission::Deny(answer.to_string()),
+        })
+    }
+
+    /// Prints a numbered menu and reads the numbers of the chosen options, separated by
+    /// spaces or commas for a multiple choice. Invalid answers are asked again.
+    async fn select(
+        &mut self,
+        text: String,
+        choices: Vec<Choice>,
+        multiple: bool,
+    ) -> Result<Vec<usize>, ConsoleError> {
+        if !matches!(self.state, State::Responding(_)) || choices.is_empty() {
+            return Err(ConsoleError::InvalidState);
+        }
+        self.check_cancelled()?;
+        self.close_line()?;
+        writeln!(self.output, "{}", text).map_err(|_| ConsoleError::Terminated)?;
+        for (index, choice) in choices.iter().enumerate() {
+            writeln!(self.output, "  {}. {}", index + 1, choice.label)
+                .map_err(|_| ConsoleError::Terminated)?;
+            if let Some(description) = &choice.description {
+                writeln!(self.output, "     {}", description)
+                    .map_err(|_| ConsoleError::Terminated)?;
+            }
+        }
+        loop {
+            if multiple {
+                write!(
+                    self.output,
+                    "Enter the numbers of your choices, separated by spaces: "
+                )
             } else {
-                return Err(ConsoleError::Terminated);
+                write!(self.output, "Enter a number [1-{}]: ", choices.len())
+            }
+            .map_err(|_| ConsoleError::Terminated)?;
+            let buffer = self.read_answer().await?;
+            let numbers: Option<Vec<usize>> = buffer
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|word| !word.is_empty())
+                .map(|word| {
+                    word.parse::<usize>()
+                        .ok()
+                        .filter(|number| (1..=choices.len()).contains(number))
+                        .map(|number| number - 1)
+                })
+                .collect();
+            match numbers {
+                Some(mut indices) if multiple => {
+                    indices.sort_unstable();
+                    indices.dedup();
+                    return Ok(indices);
+                }
+                Some(indices) if indices.len() == 1 => return Ok(indices),
+                _ => writeln!(
+                    self.output,
+                    "Please enter {} between 1 and {}.",
+                    if multiple { "numbers" } else { "one number" },
+                    choices.len()
+                )
+                .map_err(|_| ConsoleError::Terminated)?,
             }
         }
     }
@@ -218,13 +806,10 @@ where
     ///
     /// It prints the given prompt text and returns the user's trimmed input.
     async fn ask_user(&mut self, text: String) -> Result<String, ConsoleError> {
-        self.writer.write_all(format!("{} ", text).as_bytes()).unwrap();
-        self.writer.flush().unwrap();
-        let mut buffer = String::new();
-        if self.reader.read_line(&mut buffer).is_ok() {
-            Ok(buffer.trim().to_string())
-        } else {
-            Err(ConsoleError::Terminated)
-        }
+        self.check_cancelled()?;
+        self.close_line()?;
+        write!(self.output, "{} ", text).map_err(|_| ConsoleError::Terminated)?;
+        let buffer = self.read_answer().await?;
+        Ok(buffer.trim().to_string())
     }
 }
\ No newline at end of file
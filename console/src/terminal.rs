@@ -1,28 +1,339 @@
-use crate::common::{Console, ConsoleError, ConsoleInput, Observability};
+pub mod activity;
+mod dialog;
+pub mod editor;
+mod events;
+pub mod markdown;
+pub mod review;
+pub mod transcript;
+
+use crate::command::CommandRegistry;
+use crate::common::{
+    CancellationToken, Choice, Console, ConsoleError, ConsoleInput, ConsoleMode, FileDiff,
+    Message, Observability, Permission, PermissionRequest, Status, ToolStatus, TurnStatistics,
+};
+use crate::history::History;
+use activity::Activity;
 use async_trait::async_trait;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
+    event::{
+        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+        MouseEventKind, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+    ExecutableCommand as _,
 };
+use dialog::{Dialog, Outcome};
+use editor::Editor;
+use events::{is_ctrl_c, EventReader, Interrupt};
+pub use events::{CrosstermEvents, EventSource};
 use ratatui::{
-    backend::CrosstermBackend,
+    backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout},
-    widgets::{Block, Borders, Paragraph},
-    Terminal as RtuTerminal,
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Widget as _},
+    Terminal as RtuTerminal, TerminalOptions, Viewport,
 };
+use review::Review;
+use std::collections::VecDeque;
 use std::io::{self, Stdout};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+use transcript::Transcript;
 
 enum Mode {
     Prompting,
     Responding,
 }
 
+/// The input box never takes more than this part of the screen height.
+const MAX_INPUT_SHARE: u16 = 2;
+
+/// The number of transcript rows scrolled by one mouse wheel step.
+const WHEEL_SCROLL_ROWS: usize = 3;
+
+/// The state of a Ctrl-R reverse incremental search.
+struct Search {
+    query: String,
+    /// the index of the history entry shown in the input
+    found: Option<usize>,
+    /// the input before the search, restored when it is cancelled
+    original: String,
+}
+
+/// A key press or a bracketed paste.
+enum Input {
+    Key(KeyEvent),
+    Paste(String),
+}
+
+/// The kind of streamed text the last message is receiving.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Streaming {
+    Response,
+    Thinking,
+}
+
+/// Tool results longer than this many lines are collapsed in the transcript.
+const COLLAPSED_LINES: usize = 5;
+
+/// How the text of a transcript message is drawn.
+enum Format {
+    Plain,
+    Markdown,
+    /// a unified diff, with colored additions and removals
+    Diff,
+    /// plain text in a single style
+    Styled(Style),
+    /// plain text in a single style, cut after `COLLAPSED_LINES` lines
+    Collapsed(Style),
+    /// the output of a tool call below its status line
+    Tool(Activity),
+}
+
+impl Format {
+    /// Returns the format of a typed message.
+    fn of(message: &Message) -> Self {
+        match message {
+            Message::Text(_) | Message::Code { .. } | Message::Table { .. } => Format::Markdown,
+            Message::Diff { .. } => Format::Diff,
+            Message::ToolCall { .. } => {
+                Format::Styled(Style::default().add_modifier(Modifier::DIM))
+            }
+            Message::ToolResult { is_error, .. } => Format::Collapsed(if *is_error {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            }),
+            Message::Error(_) => Format::Styled(Style::default().fg(Color::Red)),
+            Message::Warning(_) => Format::Styled(Style::default().fg(Color::Yellow)),
+        }
+    }
+}
+
+/// Returns the style of a line of a unified diff.
+fn diff_style(line: &str) -> Style {
+    if line.starts_with("+++") || line.starts_with("---") || line.starts_with("diff ") {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else if line.starts_with('+') {
+        Style::default().fg(Color::Green)
+    } else if line.starts_with('-') {
+        Style::default().fg(Color::Red)
+    } else if line.starts_with("@@") {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    }
+}
+
+/// A transcript message: its raw text, kept for copying and exporting, and the lines it
+/// is drawn as.
+///
+/// The lines are wrapped to the width of the transcript once, the rows are kept until the
+/// width or the lines change.
+struct Entry {
+    text: String,
+    format: Format,
+    lines: Vec<Line<'static>>,
+    /// the finished part of a markdown text
+    progress: markdown::Progress,
+    /// the width of the rows
+    width: usize,
+    rows: Vec<Line<'static>>,
+    /// the index of the first row of each wrapped line
+    line_rows: Vec<usize>,
+}
+
+impl Entry {
+    fn new(text: String, format: Format) -> Self {
+        let mut entry = Self {
+            text,
+            format,
+            lines: Vec::new(),
+            progress: markdown::Progress::default(),
+            width: 0,
+            rows: Vec::new(),
+            line_rows: Vec::new(),
+        };
+        entry.render();
+        entry
+    }
+
+    /// Appends a fragment, rendering again only the lines it can change: the last block
+    /// of a markdown text, the last line of a text drawn line by line.
+    fn push_str(&mut self, delta: &str) {
+        let end = self.text.len();
+        self.text.push_str(delta);
+        let kept = match &self.format {
+            Format::Markdown => {
+                markdown::render_growing(&self.text, &mut self.lines, &mut self.progress)
+            }
+            Format::Plain | Format::Diff | Format::Styled(_) => {
+                let start = self.text[..end].rfind('\n').map_or(0, |index| index + 1);
+                let kept = if start == 0 { 0 } else { self.lines.len() - 1 };
+                let lines: Vec<_> = self.text[start..]
+                    .split('\n')
+                    .map(|line| self.line(line))
+                    .collect();
+                self.lines.truncate(kept);
+                self.lines.extend(lines);
+                kept
+            }
+            Format::Collapsed(_) | Format::Tool(_) => {
+                self.render();
+                return;
+            }
+        };
+        self.forget_rows(kept);
+    }
+
+    /// Returns the lines wrapped to `width`, wrapping only the lines that changed.
+    fn rows(&mut self, width: usize) -> &[Line<'static>] {
+        if width != self.width {
+            self.width = width;
+            self.forget_rows(0);
+        }
+        for line in &self.lines[self.line_rows.len()..] {
+            self.line_rows.push(self.rows.len());
+            self.rows.extend(transcript::wrap_line(line, width));
+        }
+        &self.rows
+    }
+
+    /// Drops the rows of the lines after the first `kept` ones.
+    fn forget_rows(&mut self, kept: usize) {
+        if let Some(&row) = self.line_rows.get(kept) {
+            self.rows.truncate(row);
+            self.line_rows.truncate(kept);
+        }
+    }
+
+    /// Returns the tool call shown by the message, if any.
+    fn activity(&mut self) -> Option<&mut Activity> {
+        match &mut self.format {
+            Format::Tool(activity) => Some(activity),
+            _ => None,
+        }
+    }
+
+    /// Returns a line of a text drawn line by line.
+    fn line(&self, line: &str) -> Line<'static> {
+        match &self.format {
+            Format::Diff => Line::styled(line.to_string(), diff_style(line)),
+            Format::Styled(style) => Line::styled(line.to_string(), *style),
+            _ => Line::raw(line.to_string()),
+        }
+    }
+
+    /// Renders the whole text again.
+    fn render(&mut self) {
+        self.progress = markdown::Progress::default();
+        self.forget_rows(0);
+        let lines = self.text.split('\n');
+        self.lines = match &self.format {
+            Format::Plain | Format::Diff | Format::Styled(_) => {
+                lines.map(|line| self.line(line)).collect()
+            }
+            Format::Markdown => markdown::render(&self.text),
+            Format::Collapsed(style) => {
+                let mut collapsed: Vec<_> = lines
+                    .clone()
+                    .take(COLLAPSED_LINES)
+                    .map(|line| Line::styled(line.to_string(), *style))
+                    .collect();
+                let hidden = lines.count().saturating_sub(COLLAPSED_LINES);
+                if hidden > 0 {
+                    collapsed.push(Line::styled(
+                        format!("… {} more lines", hidden),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ));
+                }
+                collapsed
+            }
+            Format::Tool(activity) => activity.render(&self.text),
+        };
+    }
+}
+
 struct State {
     mode: Mode,
     observability: Observability,
-    input_text: String,
-    messages: Vec<String>,
+    status: Status,
+    input: Editor,
+    messages: Vec<Entry>,
+    transcript: Transcript,
+    streaming: Option<Streaming>,
+    cancellation: Option<CancellationToken>,
+    dialog: Option<Dialog>,
+    review: Option<Review>,
+    search: Option<Search>,
+    /// the index of the message of the running tool call
+    tool_call: Option<usize>,
+    /// whether the output of finished tool calls is shown
+    expand_tools: bool,
+}
+
+impl State {
+    /// Adds a complete message, ending any streamed message.
+    fn push_entry(&mut self, entry: Entry) {
+        self.streaming = None;
+        self.messages.push(entry);
+        self.transcript.output_added();
+    }
+
+    /// Adds a plain text message.
+    fn push_message(&mut self, message: String) {
+        self.push_entry(Entry::new(message, Format::Plain));
+    }
+
+    /// Appends a fragment to the streamed message of the same kind or starts a new one.
+    ///
+    /// Responses are markdown, their last blocks are rendered again as they grow.
+    fn push_delta(&mut self, kind: Streaming, delta: &str) {
+        match self.messages.last_mut() {
+            Some(last) if self.streaming == Some(kind) => last.push_str(delta),
+            _ => {
+                let entry = match kind {
+                    Streaming::Response => Entry::new(delta.to_string(), Format::Markdown),
+                    Streaming::Thinking => {
+                        Entry::new(format!("[thinking] {}", delta), Format::Plain)
+                    }
+                };
+                self.messages.push(entry);
+                self.streaming = Some(kind);
+            }
+        }
+        self.transcript.output_added();
+    }
+
+    /// Returns the message of the running tool call.
+    fn running_tool(&mut self) -> Option<&mut Entry> {
+        self.messages.get_mut(self.tool_call?)
+    }
+
+    /// Returns the mode shown in the status line, a dialog or a review waits for the user.
+    fn console_mode(&self) -> ConsoleMode {
+        match self.mode {
+            _ if self.dialog.is_some() || self.review.is_some() => ConsoleMode::AwaitingApproval,
+            Mode::Prompting => ConsoleMode::Prompting,
+            Mode::Responding => ConsoleMode::Responding,
+        }
+    }
+
+    /// Shows or hides the output of all tool calls.
+    fn toggle_tool_output(&mut self) {
+        self.expand_tools = !self.expand_tools;
+        for entry in &mut self.messages {
+            if let Some(activity) = entry.activity() {
+                activity.set_expanded(self.expand_tools);
+                entry.render();
+            }
+        }
+    }
 }
 
 impl Default for State {
@@ -30,98 +341,659 @@ impl Default for State {
         Self {
             mode: Mode::Prompting,
             observability: Observability::default(),
-            input_text: String::new(),
+            status: Status::default(),
+            input: Editor::new(),
             messages: Vec::new(),
+            transcript: Transcript::new(),
+            streaming: None,
+            cancellation: None,
+            dialog: None,
+            review: None,
+            search: None,
+            tool_call: None,
+            expand_tools: false,
+        }
+    }
+}
+
+/// The modes of the real terminal set up by `Terminal::new` and `Terminal::inline`,
+/// restored when dropped.
+struct Screen {
+    keyboard_enhancement: bool,
+    /// whether the alternate screen and the mouse capture are on, the inline mode leaves
+    /// them off so that the terminal keeps its scrollback and native text selection
+    alternate: bool,
+}
+
+impl Screen {
+    /// Switches the terminal to raw mode and with `alternate` to the alternate screen.
+    fn enter(alternate: bool) -> Result<Self, io::Error> {
+        enable_raw_mode()?;
+        if alternate {
+            io::stdout().execute(EnterAlternateScreen)?;
+            io::stdout().execute(EnableMouseCapture)?;
         }
+        io::stdout().execute(EnableBracketedPaste)?;
+        // lets terminals supporting it report Shift+Enter apart from Enter
+        let keyboard_enhancement = supports_keyboard_enhancement().unwrap_or(false);
+        if keyboard_enhancement {
+            io::stdout().execute(PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES,
+            ))?;
+        }
+        Ok(Self {
+            keyboard_enhancement,
+            alternate,
+        })
     }
 }
 
-pub struct Terminal {
-    terminal: RtuTerminal<CrosstermBackend<Stdout>>,
+impl Drop for Screen {
+    fn drop(&mut self) {
+        // It's good practice to ignore errors during drop, as panicking in drop is problematic.
+        if self.keyboard_enhancement {
+            let _ = io::stdout().execute(PopKeyboardEnhancementFlags);
+        }
+        let _ = io::stdout().execute(DisableBracketedPaste);
+        if self.alternate {
+            let _ = io::stdout().execute(DisableMouseCapture);
+            let _ = io::stdout().execute(LeaveAlternateScreen);
+        }
+        let _ = disable_raw_mode();
+    }
+}
+
+/// A `Console` drawn with ratatui, full-screen or inline.
+///
+/// `Terminal::new` runs on the alternate screen of the real terminal. `Terminal::inline`
+/// draws a viewport of a fixed height at the bottom of the normal screen instead and
+/// writes the finished messages above it, into the terminal scrollback, where they stay
+/// after the session ends. Any other ratatui `Backend` and `EventSource` can be given to
+/// `Terminal::with_backend` and `Terminal::inline_with_backend`, e.g. a `TestBackend` and
+/// a channel of scripted key presses for tests.
+pub struct Terminal<B: Backend = CrosstermBackend<Stdout>> {
+    terminal: RtuTerminal<B>,
     state: State,
+    commands: CommandRegistry,
+    history: History,
+    events: UnboundedReceiver<Event>,
+    /// events received while responding, kept for the next prompt
+    pending: VecDeque<Event>,
+    interrupt: Interrupt,
+    /// in inline mode, the number of messages already written into the scrollback
+    scrollback: Option<usize>,
+    _reader: EventReader,
+    /// the modes of the real terminal, `None` with other backends
+    _screen: Option<Screen>,
 }
 
 impl Terminal {
+    /// Switches the terminal to raw mode and the alternate screen.
+    ///
+    /// Terminal events are read on a background thread, Ctrl-C and Esc pressed while
+    /// responding cancel the turn in flight.
     pub fn new() -> Result<Self, io::Error> {
-        enable_raw_mode()?;
-        io::stdout().execute(EnterAlternateScreen)?;
-        let terminal = RtuTerminal::new(CrosstermBackend::new(io::stdout()))?;
+        let screen = Screen::enter(true)?;
+        Self::create(
+            CrosstermBackend::new(io::stdout()),
+            CrosstermEvents,
+            Viewport::Fullscreen,
+            Some(screen),
+        )
+    }
+
+    /// Switches the terminal to raw mode and draws a viewport of `height` rows below the
+    /// cursor, keeping the normal screen.
+    ///
+    /// The finished messages are written above the viewport, so they can be scrolled back
+    /// to, selected and copied with the tools of the terminal, also after the session.
+    pub fn inline(height: u16) -> Result<Self, io::Error> {
+        let screen = Screen::enter(false)?;
+        Self::create(
+            CrosstermBackend::new(io::stdout()),
+            CrosstermEvents,
+            Viewport::Inline(height),
+            Some(screen),
+        )
+    }
+}
+
+impl<B: Backend> Terminal<B> {
+    /// Creates a console drawing on `backend` and reading the events of `events`.
+    ///
+    /// The terminal modes are left as they are. The events are read on a background
+    /// thread, Ctrl-C and Esc pressed while responding cancel the turn in flight.
+    pub fn with_backend(backend: B, events: impl EventSource) -> Result<Self, io::Error> {
+        Self::create(backend, events, Viewport::Fullscreen, None)
+    }
+
+    /// Creates a console drawing an inline viewport of `height` rows on `backend`, like
+    /// `Terminal::inline`, and reading the events of `events`.
+    pub fn inline_with_backend(
+        backend: B,
+        events: impl EventSource,
+        height: u16,
+    ) -> Result<Self, io::Error> {
+        Self::create(backend, events, Viewport::Inline(height), None)
+    }
+
+    fn create(
+        backend: B,
+        events: impl EventSource,
+        viewport: Viewport,
+        screen: Option<Screen>,
+    ) -> Result<Self, io::Error> {
+        let scrollback = matches!(viewport, Viewport::Inline(_)).then_some(0);
+        let terminal = RtuTerminal::with_options(backend, TerminalOptions { viewport })?;
+        let interrupt = Interrupt::default();
+        let (reader, events) = EventReader::spawn(interrupt.clone(), events);
         Ok(Self {
             terminal,
             state: State::default(),
+            commands: CommandRegistry::default(),
+            history: History::in_memory(),
+            events,
+            pending: VecDeque::new(),
+            interrupt,
+            scrollback,
+            _reader: reader,
+            _screen: screen,
         })
     }
 
+    /// Returns the backend, e.g. to check the buffer of a `TestBackend`.
+    pub fn backend(&self) -> &B {
+        self.terminal.backend()
+    }
+
+    /// Replaces the prompt history, e.g. with the history file of the project.
+    pub fn with_history(mut self, history: History) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Returns the prompt history.
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Shows the newest history entry matching the search query older than `before`.
+    fn search_history(&mut self, before: usize) {
+        let Some(search) = &mut self.state.search else {
+            return;
+        };
+        if let Some(index) = self.history.search(&search.query, before) {
+            search.found = Some(index);
+            self.state.input.set_text(&self.history.entries()[index]);
+        } else if before == self.history.entries().len() {
+            search.found = None;
+        }
+    }
+
+    /// Handles a key press during a Ctrl-R search.
+    ///
+    /// Typing refines the query, Ctrl-R looks for an older match, Enter keeps the match
+    /// in the input for editing and Esc, Ctrl-G or Ctrl-C restore the original input.
+    fn search_key(&mut self, key: KeyEvent) {
+        let Some(search) = &mut self.state.search else {
+            return;
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let cancel =
+            key.code == KeyCode::Esc || ctrl && matches!(key.code, KeyCode::Char('g' | 'c'));
+        match key.code {
+            _ if cancel => {
+                let original = std::mem::take(&mut search.original);
+                self.state.input.set_text(&original);
+                self.state.search = None;
+            }
+            KeyCode::Char('r') if ctrl => {
+                let before = search.found.unwrap_or(self.history.entries().len());
+                self.search_history(before);
+            }
+            KeyCode::Char(c) if !ctrl => {
+                search.query.push(c);
+                self.search_history(self.history.entries().len());
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                self.search_history(self.history.entries().len());
+            }
+            KeyCode::Enter | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End => {
+                self.state.search = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the raw text of the transcript, with the markdown of the responses, one
+    /// message per paragraph.
+    pub fn export_transcript(&self) -> String {
+        let texts: Vec<&str> = self
+            .state
+            .messages
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .collect();
+        texts.join("\n\n")
+    }
+
+    /// Waits for the next key press, ignoring pasted text.
+    async fn next_key(&mut self) -> Result<KeyEvent, ConsoleError> {
+        loop {
+            if let Input::Key(key) = self.next_input().await? {
+                return Ok(key);
+            }
+        }
+    }
+
+    /// Waits for the next key press or pasted text.
+    ///
+    /// Fails with `ConsoleError::Cancelled` when the turn in flight is interrupted and with
+    /// `ConsoleError::Terminated` when the event reader has stopped.
+    async fn next_input(&mut self) -> Result<Input, ConsoleError> {
+        loop {
+            let event = match (self.pending.pop_front(), self.state.cancellation.clone()) {
+                (Some(event), _) => Some(event),
+                (None, Some(token)) => tokio::select! {
+                    biased;
+                    _ = token.cancelled() => return Err(ConsoleError::Cancelled),
+                    event = self.events.recv() => event,
+                },
+                (None, None) => self.events.recv().await,
+            };
+            let Some(event) = event else {
+                return Err(ConsoleError::Terminated);
+            };
+            if self.handle_view_event(&event) {
+                self.draw().map_err(|_| ConsoleError::Terminated)?;
+                continue;
+            }
+            match event {
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    return Ok(Input::Key(key));
+                }
+                Event::Paste(text) => return Ok(Input::Paste(text)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Scrolls the transcript on PageUp/PageDown and the mouse wheel and expands the tool
+    /// output on Ctrl-O, returns `true` if the event only concerns the view, including
+    /// resizes.
+    fn handle_view_event(&mut self, event: &Event) -> bool {
+        // the keys go to an open review, the mouse wheel scrolls it
+        if let Some(review) = &mut self.state.review {
+            match event {
+                Event::Mouse(mouse) => match mouse.kind {
+                    MouseEventKind::ScrollUp => review.scroll_up(WHEEL_SCROLL_ROWS),
+                    MouseEventKind::ScrollDown => review.scroll_down(WHEEL_SCROLL_ROWS),
+                    _ => {}
+                },
+                Event::Resize(..) => {}
+                _ => return false,
+            }
+            return true;
+        }
+        let transcript = &mut self.state.transcript;
+        match event {
+            Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                KeyCode::PageUp => transcript.page_up(),
+                KeyCode::PageDown => transcript.page_down(),
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.state.toggle_tool_output()
+                }
+                _ => return false,
+            },
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::ScrollUp => transcript.scroll_up(WHEEL_SCROLL_ROWS),
+                MouseEventKind::ScrollDown => transcript.scroll_down(WHEEL_SCROLL_ROWS),
+                _ => {}
+            },
+            Event::Resize(..) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Applies the scroll and resize events received while responding and redraws.
+    ///
+    /// Other events are kept for the next prompt, so typing ahead is not lost.
+    fn refresh(&mut self) -> Result<(), ConsoleError> {
+        while let Ok(event) = self.events.try_recv() {
+            if !self.handle_view_event(&event) {
+                self.pending.push_back(event);
+            }
+        }
+        self.draw().map_err(|_| ConsoleError::Terminated)
+    }
+
+    /// In inline mode, writes the finished messages above the viewport, into the terminal
+    /// scrollback.
+    ///
+    /// A message is finished once it no longer changes: the message being streamed and the
+    /// one of the running tool call, with the messages after them, stay in the viewport.
+    fn write_scrollback(&mut self) -> Result<(), io::Error> {
+        let Some(written) = self.scrollback else {
+            return Ok(());
+        };
+        let mut finished = self.state.messages.len();
+        if self.state.streaming.is_some() {
+            finished = finished.saturating_sub(1);
+        }
+        if let Some(tool_call) = self.state.tool_call {
+            finished = finished.min(tool_call);
+        }
+        if finished <= written {
+            return Ok(());
+        }
+        let width = self.terminal.size()?.width;
+        let viewport = self.terminal.get_frame().size();
+        // the rows are inserted at most one free screen at a time, above the viewport
+        let chunk_height = self.terminal.size()?.height.saturating_sub(viewport.height);
+        if chunk_height == 0 {
+            return Ok(());
+        }
+        let rows: Vec<Line<'static>> = self.state.messages[written..finished]
+            .iter_mut()
+            .flat_map(|entry| entry.rows(width as usize).to_vec())
+            .collect();
+        for chunk in rows.chunks(chunk_height as usize) {
+            self.terminal.insert_before(chunk.len() as u16, |buffer| {
+                Paragraph::new(chunk.to_vec()).render(buffer.area, buffer);
+            })?;
+        }
+        self.scrollback = Some(finished);
+        self.state.transcript.follow();
+        Ok(())
+    }
+
     fn draw(&mut self) -> Result<(), io::Error> {
+        self.write_scrollback()?;
+        let written = self.scrollback.unwrap_or(0);
         self.terminal.draw(|frame| {
+            let area = frame.size();
+            let input = self
+                .state
+                .input
+                .layout(area.width.saturating_sub(2) as usize);
+            let max_rows = (area.height / MAX_INPUT_SHARE).saturating_sub(2).max(1) as usize;
+            let visible_rows = input.rows.len().min(max_rows);
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Constraint::Min(1), Constraint::Length(3)].as_ref())
-                .split(frame.size());
+                .constraints(
+                    [
+                        Constraint::Min(1),
+                        Constraint::Length(visible_rows as u16 + 2),
+                    ]
+                    .as_ref(),
+                )
+                .split(area);
 
-            let messages = self.state.messages.join("\n");
-            let message_paragraph = Paragraph::new(messages)
-                .block(Block::default().borders(Borders::ALL).title("Messages"));
-            frame.render_widget(message_paragraph, chunks[0]);
+            let width = chunks[0].width.saturating_sub(2) as usize;
+            let rows = self.state.transcript.view_rows(
+                self.state.messages[written..]
+                    .iter_mut()
+                    .map(|entry| entry.rows(width)),
+                chunks[0].height.saturating_sub(2) as usize,
+            );
+            let mut block = Block::default().borders(Borders::ALL).title("Messages");
+            if self.state.transcript.has_unseen_output() {
+                block = block.title_bottom(
+                    Line::styled(
+                        " ↓ new output below (PageDown) ",
+                        Style::default().fg(Color::Yellow),
+                    )
+                    .right_aligned(),
+                );
+            }
+            frame.render_widget(Paragraph::new(rows).block(block), chunks[0]);
 
-            let input_paragraph = Paragraph::new(self.state.input_text.as_str())
-                .block(Block::default().borders(Borders::ALL).title("Input"));
+            // scrolls the input so that the cursor row stays visible
+            let (cursor_row, cursor_column) = input.cursor;
+            let first_row = (cursor_row + 1).saturating_sub(visible_rows);
+            let rows: Vec<Line> = input.rows[first_row..first_row + visible_rows]
+                .iter()
+                .map(|row| Line::raw(row.as_str()))
+                .collect();
+            let title = match &self.state.search {
+                Some(search) if search.found.is_none() && !search.query.is_empty() => {
+                    format!("Input (failing reverse-i-search: {})", search.query)
+                }
+                Some(search) => format!("Input (reverse-i-search: {})", search.query),
+                None => "Input".to_string(),
+            };
+            let status = Line::styled(
+                format!(" {} ", self.state.status.line(self.state.console_mode())),
+                Style::default().add_modifier(Modifier::DIM),
+            );
+            let input_paragraph = Paragraph::new(rows).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title_bottom(status),
+            );
             frame.render_widget(input_paragraph, chunks[1]);
+            if matches!(self.state.mode, Mode::Prompting)
+                && self.state.dialog.is_none()
+                && self.state.review.is_none()
+            {
+                frame.set_cursor(
+                    chunks[1].x + 1 + cursor_column as u16,
+                    chunks[1].y + 1 + (cursor_row - first_row) as u16,
+                );
+            }
+
+            if let Some(review) = &mut self.state.review {
+                review.render(frame, frame.size());
+            }
+            if let Some(dialog) = &self.state.dialog {
+                dialog.render(frame, frame.size());
+            }
         })?;
         Ok(())
     }
+
+    /// Shows a modal dialog and waits until the user closes it.
+    ///
+    /// Esc is handled by the dialog while it is open, Ctrl-C still cancels the turn in
+    /// flight and otherwise closes the dialog as cancelled.
+    async fn run_dialog(&mut self, dialog: Dialog) -> Result<Outcome, ConsoleError> {
+        self.state.dialog = Some(dialog);
+        self.interrupt.set_modal(true);
+        let outcome = self.dialog_loop().await;
+        self.interrupt.set_modal(false);
+        self.state.dialog = None;
+        self.draw().map_err(|_| ConsoleError::Terminated)?;
+        outcome
+    }
+
+    async fn dialog_loop(&mut self) -> Result<Outcome, ConsoleError> {
+        loop {
+            self.draw().map_err(|_| ConsoleError::Terminated)?;
+            let key = self.next_key().await?;
+            if is_ctrl_c(&key) {
+                return Ok(Outcome::Cancelled);
+            }
+            let Some(dialog) = self.state.dialog.as_mut() else {
+                return Err(ConsoleError::InvalidState);
+            };
+            if let Some(outcome) = dialog.handle_key(key) {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    /// Shows the review viewer until the user is done, Ctrl-C cancels the review.
+    async fn review_loop(&mut self) -> Result<Vec<FileDiff>, ConsoleError> {
+        loop {
+            self.draw().map_err(|_| ConsoleError::Terminated)?;
+            let key = self.next_key().await?;
+            if is_ctrl_c(&key) {
+                return Err(ConsoleError::Cancelled);
+            }
+            let Some(review) = self.state.review.as_mut() else {
+                return Err(ConsoleError::InvalidState);
+            };
+            if let Some(accepted) = review.handle_key(key) {
+                return Ok(accepted);
+            }
+        }
+    }
+
+    /// Asks a yes/no question in a dialog and records the answer in the transcript.
+    async fn confirm(&mut self, text: String, default: bool) -> Result<bool, ConsoleError> {
+        if !matches!(self.state.mode, Mode::Responding) {
+            return Err(ConsoleError::InvalidState);
+        }
+        let question = format!("{} {}", text, if default { "[Y/n]" } else { "[y/N]" });
+        let answer = match self.run_dialog(Dialog::confirm(text, default)).await? {
+            Outcome::Confirmed(answer) => answer,
+            Outcome::Answered(_)
+            | Outcome::Chosen { .. }
+            | Outcome::Checked(_)
+            | Outcome::Cancelled => return Err(ConsoleError::Cancelled),
+        };
+        self.state.push_message(format!(
+            "{} {}",
+            question,
+            if answer { "yes" } else { "no" }
+        ));
+        Ok(answer)
+    }
 }
 
-impl Drop for Terminal {
+impl<B: Backend> Drop for Terminal<B> {
+    /// In inline mode, writes the remaining messages into the scrollback and clears the
+    /// viewport, the shell prompt comes back right below the conversation.
     fn drop(&mut self) {
+        if self.scrollback.is_none() {
+            return;
+        }
+        self.state.streaming = None;
+        self.state.tool_call = None;
         // It's good practice to ignore errors during drop, as panicking in drop is problematic.
-        let _ = io::stdout().execute(LeaveAlternateScreen);
-        let _ = disable_raw_mode();
+        let _ = self.write_scrollback();
+        let _ = self.terminal.clear();
     }
 }
 
 #[async_trait]
-impl Console for Terminal {
+impl<B: Backend + Send> Console for Terminal<B> {
     async fn prompt_input(&mut self) -> Result<ConsoleInput, ConsoleError> {
         loop {
             self.draw().map_err(|_| ConsoleError::Terminated)?;
 
-            if event::poll(std::time::Duration::from_millis(100))
-                .map_err(|_| ConsoleError::Terminated)?
-            {
-                if let Event::Key(key) = event::read().map_err(|_| ConsoleError::Terminated)? {
-                    if key.kind == KeyEventKind::Press {
-                        match key.code {
-                            KeyCode::Enter => {
-                                let line = self.state.input_text.clone();
-                                self.state.input_text.clear();
-                                self.state.messages.push(format!("> {}", line));
-                                return ConsoleInput::from_line(&line);
-                            }
-                            KeyCode::Char(c) => {
-                                self.state.input_text.push(c);
-                            }
-                            KeyCode::Backspace => {
-                                self.state.input_text.pop();
-                            }
-                            _ => {}
+            let key = match self.next_input().await? {
+                Input::Key(key) if self.state.search.is_some() => {
+                    self.search_key(key);
+                    continue;
+                }
+                Input::Key(key) => key,
+                Input::Paste(text) => {
+                    self.state.input.insert_str(&text);
+                    continue;
+                }
+            };
+            let newline = key
+                .modifiers
+                .intersects(KeyModifiers::SHIFT | KeyModifiers::ALT);
+            match key.code {
+                // Ctrl-C clears the input, on an empty input it ends the session like Ctrl-D
+                _ if is_ctrl_c(&key) && !self.state.input.is_empty() => {
+                    self.state.input.clear();
+                    self.history.reset_navigation();
+                }
+                _ if is_ctrl_c(&key) => return Err(ConsoleError::Terminated),
+                KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if self.state.input.is_empty() {
+                        return Err(ConsoleError::Terminated);
+                    }
+                    self.state.input.delete_forward();
+                }
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.state.search = Some(Search {
+                        query: String::new(),
+                        found: None,
+                        original: self.state.input.text().to_string(),
+                    });
+                }
+                // the cursor moves between the lines of the input, the history is browsed
+                // from its first and last line
+                KeyCode::Up => {
+                    if !self.state.input.move_up() {
+                        if let Some(entry) = self.history.previous_entry(self.state.input.text()) {
+                            self.state.input.set_text(entry);
+                        }
+                    }
+                }
+                KeyCode::Down => {
+                    if !self.state.input.move_down() {
+                        if let Some(entry) = self.history.next_entry() {
+                            self.state.input.set_text(entry);
                         }
                     }
                 }
+                KeyCode::Enter if newline => self.state.input.insert_newline(),
+                // like StdIo, a blank input is not a prompt
+                KeyCode::Enter if self.state.input.text().trim().is_empty() => {}
+                KeyCode::Enter => {
+                    let line = self.state.input.take();
+                    self.state.transcript.follow();
+                    self.state.push_message(format!("> {}", line));
+                    if let Err(err) = self.history.add(&line) {
+                        self.state
+                            .push_message(format!("Failed to save the history: {}", err));
+                    }
+                    match self.commands.parse(&line)? {
+                        ConsoleInput::Help => self.state.push_message(self.commands.help()),
+                        ConsoleInput::Status => self
+                            .state
+                            .push_message(self.state.status.line(ConsoleMode::Prompting)),
+                        input => return Ok(input),
+                    }
+                }
+                _ => {
+                    self.state.input.handle_key(key);
+                }
             }
         }
     }
 
     async fn start_responding(&mut self) -> Result<(), ConsoleError> {
+        let cancellation = CancellationToken::new();
+        self.interrupt.set(Some(cancellation.clone()));
+        self.state.cancellation = Some(cancellation);
         self.state.mode = Mode::Responding;
         Ok(())
     }
 
+    /// Switches back to the prompt, a tool call still running was interrupted.
     async fn stop_responding(&mut self) -> Result<(), ConsoleError> {
+        if let Some(entry) = self.state.running_tool() {
+            if let Some(activity) = entry.activity() {
+                let duration = activity.elapsed();
+                activity.finish(ToolStatus::Failed("interrupted".to_string()), duration);
+                entry.render();
+            }
+        }
+        self.state.tool_call = None;
+        self.interrupt.set(None);
+        self.state.cancellation = None;
         self.state.mode = Mode::Prompting;
+        self.state.streaming = None;
         Ok(())
     }
 
+    fn cancellation_token(&self) -> Option<CancellationToken> {
+        self.state.cancellation.clone()
+    }
+
+    fn commands(&mut self) -> &mut CommandRegistry {
+        &mut self.commands
+    }
+
     fn observability(&mut self, new_settings: Option<Observability>) -> Observability {
         if let Some(new_observability) = new_settings {
             self.state.observability = new_observability;
@@ -129,52 +1001,222 @@ impl Console for Terminal {
         self.state.observability
     }
 
+    fn status(&mut self) -> &mut Status {
+        &mut self.state.status
+    }
+
+    /// Adds a response to the transcript, rendered as markdown.
     async fn add_response_text(&mut self, text: String) -> Result<(), ConsoleError> {
-        self.state.messages.push(text);
-        self.draw().map_err(|_| ConsoleError::Terminated)
+        self.state.push_entry(Entry::new(text, Format::Markdown));
+        self.refresh()
     }
 
     async fn add_thinking_text(&mut self, text: String) -> Result<(), ConsoleError> {
         // For now, just add it to messages. We can make this fancier later.
         if self.state.observability.thinking {
-            self.state.messages.push(format!("[thinking] {}", text));
-            self.draw().map_err(|_| ConsoleError::Terminated)?;
+            self.state.push_message(format!("[thinking] {}", text));
+            self.refresh()?;
         }
         Ok(())
     }
 
+    async fn add_response_delta(&mut self, delta: String) -> Result<(), ConsoleError> {
+        self.state.push_delta(Streaming::Response, &delta);
+        self.refresh()
+    }
+
+    async fn add_thinking_delta(&mut self, delta: String) -> Result<(), ConsoleError> {
+        if self.state.observability.thinking {
+            self.state.push_delta(Streaming::Thinking, &delta);
+            self.refresh()?;
+        }
+        Ok(())
+    }
+
+    /// Adds a typed message to the transcript: diffs and errors are colored, long tool
+    /// results are collapsed. The raw text is kept in full for exporting.
+    async fn add_message(&mut self, message: Message) -> Result<(), ConsoleError> {
+        let format = Format::of(&message);
+        self.state
+            .push_entry(Entry::new(message.to_string(), format));
+        self.refresh()
+    }
+
+    /// Adds a spinner line for the tool call, it is animated by every output fragment.
+    async fn start_tool_call(
+        &mut self,
+        name: String,
+        arguments: String,
+    ) -> Result<(), ConsoleError> {
+        if !matches!(self.state.mode, Mode::Responding) || self.state.tool_call.is_some() {
+            return Err(ConsoleError::InvalidState);
+        }
+        let mut activity = Activity::new(name, arguments);
+        activity.set_expanded(self.state.expand_tools);
+        self.state
+            .push_entry(Entry::new(String::new(), Format::Tool(activity)));
+        self.state.tool_call = Some(self.state.messages.len() - 1);
+        self.refresh()
+    }
+
+    /// Appends to the output of the tool call, the last lines are shown below the spinner.
+    async fn add_tool_output(&mut self, output: String) -> Result<(), ConsoleError> {
+        let Some(entry) = self.state.running_tool() else {
+            return Err(ConsoleError::InvalidState);
+        };
+        entry.push_str(&output);
+        if !output.is_empty() {
+            self.state.transcript.output_added();
+        }
+        self.refresh()
+    }
+
+    /// Collapses the tool call into a one-line summary, Ctrl-O expands the output.
+    async fn finish_tool_call(
+        &mut self,
+        status: ToolStatus,
+        duration: Duration,
+    ) -> Result<(), ConsoleError> {
+        let Some(entry) = self.state.running_tool() else {
+            return Err(ConsoleError::InvalidState);
+        };
+        if let Some(activity) = entry.activity() {
+            activity.finish(status, duration);
+        }
+        entry.render();
+        self.state.tool_call = None;
+        self.state.transcript.output_added();
+        self.refresh()
+    }
+
+    /// Adds the statistics of the turn to the transcript, dimmed, if statistics are enabled.
+    async fn add_statistics(&mut self, statistics: TurnStatistics) -> Result<(), ConsoleError> {
+        if self.state.observability.statistics {
+            self.state.push_entry(Entry::new(
+                statistics.to_string(),
+                Format::Styled(Style::default().add_modifier(Modifier::DIM)),
+            ));
+            self.refresh()?;
+        }
+        Ok(())
+    }
+
+    /// Asks the user to accept an action in a dialog, defaulting to "yes".
     async fn if_accept(&mut self, text: String) -> Result<bool, ConsoleError> {
-        self.if_yes(text).await
+        self.confirm(text, true).await
     }
 
-    async fn if_yes(&mut self, text: String) -> Result<bool, ConsoleError> {
-        // This is a simplified implementation for the terminal UI.
-        // A real implementation would involve a modal dialog or similar.
-        self.state.messages.push(format!("{} [Y/n]", text));
-        self.draw().map_err(|_| ConsoleError::Terminated)?;
+    /// Reviews the changes in a full-screen diff viewer and records the number of
+    /// accepted hunks in the transcript.
+    async fn review_diff(&mut self, files: Vec<FileDiff>) -> Result<Vec<FileDiff>, ConsoleError> {
+        if !matches!(self.state.mode, Mode::Responding) {
+            return Err(ConsoleError::InvalidState);
+        }
+        let total: usize = files.iter().map(|file| file.hunks.len()).sum();
+        self.state.review = Some(Review::new(files));
+        self.interrupt.set_modal(true);
+        let result = self.review_loop().await;
+        self.interrupt.set_modal(false);
+        self.state.review = None;
+        let accepted = result?;
+        let count: usize = accepted.iter().map(|file| file.hunks.len()).sum();
+        self.state.push_message(format!(
+            "Reviewed the changes: accepted {} of {} hunks",
+            count, total
+        ));
+        self.refresh()?;
+        Ok(accepted)
+    }
 
-        loop {
-            if event::poll(std::time::Duration::from_millis(100))
-                .map_err(|_| ConsoleError::Terminated)?
-            {
-                if let Event::Key(key) = event::read().map_err(|_| ConsoleError::Terminated)? {
-                    if key.kind == KeyEventKind::Press {
-                        match key.code {
-                            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
-                                return Ok(true);
-                            }
-                            KeyCode::Char('n') | KeyCode::Char('N') => {
-                                return Ok(false);
-                            }
-                            _ => {}
-                        }
-                    }
-                }
+    /// Asks for a permission in a dialog listing the answers, a note typed below the last
+    /// one is the feedback of the denial. Esc denies without feedback.
+    async fn request_permission(
+        &mut self,
+        request: PermissionRequest,
+    ) -> Result<Permission, ConsoleError> {
+        if !matches!(self.state.mode, Mode::Responding) {
+            return Err(ConsoleError::InvalidState);
+        }
+        let text = format!("Allow {}: {}?", request.tool, request.subject);
+        let options = vec![
+            Choice::new("Yes, once"),
+            Choice::new(format!("Yes, for this session: {}", request.pattern)),
+            Choice::new(format!("Yes, always in this project: {}", request.pattern)),
+            Choice::new("No, and tell the agent what to do instead"),
+        ];
+        let dialog = Dialog::choice("Permission".to_string(), text.clone(), options, true);
+        let (permission, answer) = match self.run_dialog(dialog).await? {
+            Outcome::Chosen { index: 0, .. } => (Permission::AllowOnce, "allowed once".to_string()),
+            Outcome::Chosen { index: 1, .. } => (
+                Permission::AllowSession,
+                format!("allowed for the session: {}", request.pattern),
+            ),
+            Outcome::Chosen { index: 2, .. } => (
+                Permission::AllowAlways,
+                format!("allowed always: {}", request.pattern),
+            ),
+            Outcome::Chosen { note, .. } if !note.is_empty() => {
+                (Permission::Deny(note.clone()), format!("denied: {}", note))
             }
+            _ => (Permission::Deny(String::new()), "denied".to_string()),
+        };
+        self.state.push_message(format!("{} {}", text, answer));
+        self.refresh()?;
+        Ok(permission)
+    }
+
+    /// Shows the options in a dialog list and records the chosen labels in the transcript.
+    async fn select(
+        &mut self,
+        text: String,
+        choices: Vec<Choice>,
+        multiple: bool,
+    ) -> Result<Vec<usize>, ConsoleError> {
+        if !matches!(self.state.mode, Mode::Responding) || choices.is_empty() {
+            return Err(ConsoleError::InvalidState);
         }
+        let title = if multiple { "Select" } else { "Choose" }.to_string();
+        let dialog = if multiple {
+            Dialog::multiple_choice(title, text.clone(), choices.clone())
+        } else {
+            Dialog::choice(title, text.clone(), choices.clone(), false)
+        };
+        let selection = match self.run_dialog(dialog).await? {
+            Outcome::Chosen { index, .. } => vec![index],
+            Outcome::Checked(indices) => indices,
+            Outcome::Confirmed(_) | Outcome::Answered(_) | Outcome::Cancelled => {
+                return Err(ConsoleError::Cancelled);
+            }
+        };
+        let labels: Vec<&str> = selection
+            .iter()
+            .map(|index| choices[*index].label.as_str())
+            .collect();
+        self.state
+            .push_message(format!("{} {}", text, labels.join(", ")));
+        self.refresh()?;
+        Ok(selection)
     }
 
-    async fn ask_user(&mut self, _text: String) -> Result<String, ConsoleError> {
-        unimplemented!()
+    /// Asks the user a yes/no question in a dialog, defaulting to "no".
+    async fn if_yes(&mut self, text: String) -> Result<bool, ConsoleError> {
+        self.confirm(text, false).await
+    }
+
+    /// Asks the user for a line of text in a dialog, returns the trimmed answer.
+    ///
+    /// Closing the dialog with Esc or the Cancel button fails with `ConsoleError::Cancelled`.
+    async fn ask_user(&mut self, text: String) -> Result<String, ConsoleError> {
+        let question = text.clone();
+        match self.run_dialog(Dialog::question(text)).await? {
+            Outcome::Answered(answer) => {
+                self.state.push_message(format!("{} {}", question, answer));
+                Ok(answer)
+            }
+            Outcome::Confirmed(_)
+            | Outcome::Chosen { .. }
+            | Outcome::Checked(_)
+            | Outcome::Cancelled => Err(ConsoleError::Cancelled),
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,122 @@
+//! The tool call activity lines of the terminal console.
+
+use crate::common::ToolStatus;
+use crate::message::shorten;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use std::time::{Duration, Instant};
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// The spinner moves to its next frame after this time.
+const SPINNER_FRAME: Duration = Duration::from_millis(100);
+
+/// The arguments of a tool call are cut to this many characters in its header line.
+const MAX_ARGUMENTS_CHARS: usize = 60;
+
+/// The number of the last output lines shown below the spinner of a running tool call.
+const RUNNING_TAIL_LINES: usize = 3;
+
+/// A tool call shown in the transcript.
+///
+/// While the call runs, a spinner line is followed by the tail of the output. Once the
+/// call has finished, it collapses into a one-line summary, the full output is only shown
+/// when the activity is expanded.
+#[derive(Debug, Clone)]
+pub struct Activity {
+    name: String,
+    arguments: String,
+    started: Instant,
+    finished: Option<(ToolStatus, Duration)>,
+    expanded: bool,
+}
+
+impl Activity {
+    pub fn new(name: String, arguments: String) -> Self {
+        Self {
+            name,
+            arguments,
+            started: Instant::now(),
+            finished: None,
+            expanded: false,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.finished.is_none()
+    }
+
+    /// Returns the time since the call started.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn finish(&mut self, status: ToolStatus, duration: Duration) {
+        self.finished = Some((status, duration));
+    }
+
+    pub fn set_expanded(&mut self, expanded: bool) {
+        self.expanded = expanded;
+    }
+
+    /// Returns the lines of the activity with the given output of the tool.
+    pub fn render(&self, output: &str) -> Vec<Line<'static>> {
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        let call = format!(
+            "{}({})",
+            self.name,
+            shorten(&self.arguments, MAX_ARGUMENTS_CHARS)
+        );
+        let output_lines: Vec<&str> = output.lines().collect();
+        let Some((status, duration)) = &self.finished else {
+            let elapsed = self.elapsed();
+            let frame = (elapsed.as_millis() / SPINNER_FRAME.as_millis()) as usize;
+            let mut lines = vec![Line::from(vec![
+                Span::styled(
+                    SPINNER[frame % SPINNER.len()],
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(format!(" {} ", call)),
+                Span::styled(format!("{:.1}s", elapsed.as_secs_f64()), dim),
+            ])];
+            let tail = output_lines.len().saturating_sub(RUNNING_TAIL_LINES);
+            lines.extend(
+                output_lines[tail..]
+                    .iter()
+                    .map(|line| Line::styled(format!("  {}", line), dim)),
+            );
+            return lines;
+        };
+
+        let mut summary = match status {
+            ToolStatus::Succeeded => vec![Span::styled("✓", Style::default().fg(Color::Green))],
+            ToolStatus::Failed(_) => vec![Span::styled("✗", Style::default().fg(Color::Red))],
+        };
+        summary.push(Span::raw(format!(" {} ", call)));
+        summary.push(Span::styled(
+            format!("· {:.1}s", duration.as_secs_f64()),
+            dim,
+        ));
+        if let ToolStatus::Failed(error) = status {
+            summary.push(Span::styled(
+                format!(" · {}", shorten(error, MAX_ARGUMENTS_CHARS)),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        if !output_lines.is_empty() && !self.expanded {
+            summary.push(Span::styled(
+                format!(" · {} lines (Ctrl-O to expand)", output_lines.len()),
+                dim,
+            ));
+        }
+        let mut lines = vec![Line::from(summary)];
+        if self.expanded {
+            lines.extend(
+                output_lines
+                    .iter()
+                    .map(|line| Line::raw(format!("  {}", line))),
+            );
+        }
+        lines
+    }
+}
@@ -0,0 +1,436 @@
+use crate::selection::Choice;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use unicode_width::UnicodeWidthStr as _;
+
+/// The widest a dialog can be, narrower terminals get a dialog of their own width.
+const MAX_WIDTH: u16 = 72;
+
+/// The element of a dialog receiving Enter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Input,
+    Yes,
+    No,
+    Ok,
+    Cancel,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    /// a yes/no question, `default` is the answer given by Enter without moving the focus
+    Confirm { default: bool },
+    /// a free-text question
+    Question { answer: String },
+    /// a list of options, the last one may take a free-text note
+    Choice {
+        title: String,
+        options: Vec<Choice>,
+        selected: usize,
+        note: Option<String>,
+        /// the checked options when several can be chosen
+        checked: Option<Vec<bool>>,
+    },
+}
+
+/// How the user closed a dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Outcome {
+    Confirmed(bool),
+    Answered(String),
+    /// the index of the chosen option and the note of the last option, empty for the others
+    Chosen {
+        index: usize,
+        note: String,
+    },
+    /// the indices of the checked options of a multiple choice
+    Checked(Vec<usize>),
+    /// Esc on a question or a choice, or the Cancel button
+    Cancelled,
+}
+
+/// Returns the number of lines of a list of options, with their descriptions.
+fn option_rows(options: &[Choice]) -> usize {
+    options
+        .iter()
+        .map(|option| 1 + usize::from(option.description.is_some()))
+        .sum()
+}
+
+/// A modal dialog drawn over the transcript.
+///
+/// Confirmations have Yes/No buttons with the default focused, `y`/`n` answer directly and
+/// Esc answers no. Questions have an input line with OK/Cancel buttons, Esc cancels. Tab
+/// and the arrow keys move the focus. Choices are a list selected with the arrow keys or
+/// the option numbers, when the last option takes a note it is typed right below it. In a
+/// multiple choice, Space and the option numbers check the options and Enter confirms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Dialog {
+    text: String,
+    kind: Kind,
+    focus: Focus,
+}
+
+impl Dialog {
+    pub(super) fn confirm(text: String, default: bool) -> Self {
+        Self {
+            text,
+            kind: Kind::Confirm { default },
+            focus: if default { Focus::Yes } else { Focus::No },
+        }
+    }
+
+    pub(super) fn question(text: String) -> Self {
+        Self {
+            text,
+            kind: Kind::Question {
+                answer: String::new(),
+            },
+            focus: Focus::Input,
+        }
+    }
+
+    /// Creates a list of options, the last one takes a note typed by the user if `note` is
+    /// set.
+    pub(super) fn choice(title: String, text: String, options: Vec<Choice>, note: bool) -> Self {
+        Self {
+            text,
+            kind: Kind::Choice {
+                title,
+                options,
+                selected: 0,
+                note: note.then(String::new),
+                checked: None,
+            },
+            focus: Focus::Input,
+        }
+    }
+
+    /// Creates a list of options of which any number can be checked.
+    pub(super) fn multiple_choice(title: String, text: String, options: Vec<Choice>) -> Self {
+        let checked = vec![false; options.len()];
+        Self {
+            text,
+            kind: Kind::Choice {
+                title,
+                options,
+                selected: 0,
+                note: None,
+                checked: Some(checked),
+            },
+            focus: Focus::Input,
+        }
+    }
+
+    fn focus_order(&self) -> &'static [Focus] {
+        match self.kind {
+            Kind::Confirm { .. } => &[Focus::Yes, Focus::No],
+            Kind::Question { .. } => &[Focus::Input, Focus::Ok, Focus::Cancel],
+            Kind::Choice { .. } => &[Focus::Input],
+        }
+    }
+
+    fn move_focus(&mut self, forward: bool) {
+        let order = self.focus_order();
+        let index = order.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let next = if forward {
+            (index + 1) % order.len()
+        } else {
+            (index + order.len() - 1) % order.len()
+        };
+        self.focus = order[next];
+    }
+
+    /// Handles a key press, returns the outcome once the dialog is closed.
+    pub(super) fn handle_key(&mut self, key: KeyEvent) -> Option<Outcome> {
+        match key.code {
+            KeyCode::Esc => {
+                return Some(match self.kind {
+                    Kind::Confirm { .. } => Outcome::Confirmed(false),
+                    Kind::Question { .. } | Kind::Choice { .. } => Outcome::Cancelled,
+                });
+            }
+            KeyCode::Tab => self.move_focus(true),
+            KeyCode::BackTab => self.move_focus(false),
+            KeyCode::Enter => {
+                return Some(match (&self.kind, self.focus) {
+                    (_, Focus::Yes) => Outcome::Confirmed(true),
+                    (_, Focus::No) => Outcome::Confirmed(false),
+                    (_, Focus::Cancel) => Outcome::Cancelled,
+                    (Kind::Question { answer }, _) => Outcome::Answered(answer.trim().to_string()),
+                    (Kind::Confirm { default }, _) => Outcome::Confirmed(*default),
+                    (
+                        Kind::Choice {
+                            checked: Some(checked),
+                            ..
+                        },
+                        _,
+                    ) => Outcome::Checked(
+                        (0..checked.len()).filter(|index| checked[*index]).collect(),
+                    ),
+                    (
+                        Kind::Choice {
+                            options,
+                            selected,
+                            note,
+                            ..
+                        },
+                        _,
+                    ) => Outcome::Chosen {
+                        index: *selected,
+                        note: match note {
+                            Some(note) if *selected + 1 == options.len() => note.trim().to_string(),
+                            _ => String::new(),
+                        },
+                    },
+                });
+            }
+            _ => {}
+        }
+
+        match &mut self.kind {
+            Kind::Confirm { .. } => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => return Some(Outcome::Confirmed(true)),
+                KeyCode::Char('n') | KeyCode::Char('N') => {
+                    return Some(Outcome::Confirmed(false));
+                }
+                KeyCode::Left | KeyCode::Right => self.move_focus(key.code == KeyCode::Right),
+                _ => {}
+            },
+            Kind::Question { answer } => match key.code {
+                KeyCode::Char(c) if self.focus == Focus::Input => answer.push(c),
+                KeyCode::Backspace if self.focus == Focus::Input => {
+                    answer.pop();
+                }
+                KeyCode::Up | KeyCode::Down => self.move_focus(key.code == KeyCode::Down),
+                KeyCode::Left | KeyCode::Right if self.focus != Focus::Input => {
+                    self.move_focus(key.code == KeyCode::Right)
+                }
+                _ => {}
+            },
+            Kind::Choice {
+                selected,
+                checked: Some(checked),
+                ..
+            } => match key.code {
+                KeyCode::Up | KeyCode::BackTab => *selected = selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Tab => {
+                    *selected = (*selected + 1).min(checked.len().saturating_sub(1))
+                }
+                KeyCode::Char(' ') => {
+                    if let Some(checked) = checked.get_mut(*selected) {
+                        *checked = !*checked;
+                    }
+                }
+                KeyCode::Char(c) => {
+                    let index = c.to_digit(10).unwrap_or(0) as usize;
+                    if (1..=checked.len()).contains(&index) {
+                        *selected = index - 1;
+                        checked[index - 1] = !checked[index - 1];
+                    }
+                }
+                _ => {}
+            },
+            Kind::Choice {
+                options,
+                selected,
+                note,
+                ..
+            } => {
+                let last = options.len().saturating_sub(1);
+                let takes_note = note.is_some();
+                let note = note.as_mut().filter(|_| *selected == last);
+                match (key.code, note) {
+                    (KeyCode::Up | KeyCode::BackTab, _) => *selected = selected.saturating_sub(1),
+                    (KeyCode::Down | KeyCode::Tab, _) => *selected = (*selected + 1).min(last),
+                    (KeyCode::Char(c), Some(note)) => note.push(c),
+                    (KeyCode::Backspace, Some(note)) => {
+                        note.pop();
+                    }
+                    (KeyCode::Char(c), None) => {
+                        let index = c.to_digit(10).unwrap_or(0) as usize;
+                        if (1..=options.len()).contains(&index) {
+                            *selected = index - 1;
+                            // the option taking a note is only selected, to type the note
+                            if *selected < last || !takes_note {
+                                return Some(Outcome::Chosen {
+                                    index: *selected,
+                                    note: String::new(),
+                                });
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+
+    fn button(&self, label: &str, focus: Focus) -> Span<'static> {
+        let style = if self.focus == focus {
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        Span::styled(format!("[ {} ]", label), style)
+    }
+
+    /// Draws the dialog centered in `area` and places the cursor on the input line.
+    pub(super) fn render(&self, frame: &mut Frame, area: Rect) {
+        let width = area.width.min(MAX_WIDTH);
+        let inner_width = width.saturating_sub(2).max(1) as usize;
+        let text_height: usize = self
+            .text
+            .lines()
+            .map(|line| line.width().div_ceil(inner_width).max(1))
+            .sum();
+        let extra = match &self.kind {
+            Kind::Confirm { .. } => 2,
+            Kind::Question { .. } => 4,
+            Kind::Choice { options, note, .. } => {
+                option_rows(options) + usize::from(note.is_some()) + 2
+            }
+        };
+        let height = ((text_height + extra) as u16 + 2).min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let mut lines: Vec<Line> = self
+            .text
+            .lines()
+            .map(|l| Line::raw(l.to_string()))
+            .collect();
+        lines.push(Line::raw(""));
+        let (title, buttons) = match &self.kind {
+            Kind::Confirm { default } => (
+                if *default {
+                    " Confirm [Y/n] ".to_string()
+                } else {
+                    " Confirm [y/N] ".to_string()
+                },
+                vec![
+                    self.button("Yes", Focus::Yes),
+                    Span::raw("  "),
+                    self.button("No", Focus::No),
+                ],
+            ),
+            Kind::Question { answer } => {
+                let style = if self.focus == Focus::Input {
+                    Style::default().add_modifier(Modifier::UNDERLINED)
+                } else {
+                    Style::default()
+                };
+                lines.push(Line::from(vec![
+                    Span::raw("> "),
+                    Span::styled(answer.clone(), style),
+                ]));
+                lines.push(Line::raw(""));
+                (
+                    " Question ".to_string(),
+                    vec![
+                        self.button("OK", Focus::Ok),
+                        Span::raw("  "),
+                        self.button("Cancel", Focus::Cancel),
+                    ],
+                )
+            }
+            Kind::Choice {
+                title,
+                options,
+                selected,
+                note,
+                checked,
+            } => {
+                let dim = Style::default().add_modifier(Modifier::DIM);
+                for (index, option) in options.iter().enumerate() {
+                    let (marker, style) = if index == *selected {
+                        (
+                            "❯",
+                            Style::default()
+                                .fg(Color::Cyan)
+                                .add_modifier(Modifier::BOLD),
+                        )
+                    } else {
+                        (" ", Style::default())
+                    };
+                    let check = match checked {
+                        Some(checked) if checked[index] => "[x] ",
+                        Some(_) => "[ ] ",
+                        None => "",
+                    };
+                    lines.push(Line::styled(
+                        format!("{} {}{}. {}", marker, check, index + 1, option.label),
+                        style,
+                    ));
+                    if let Some(description) = &option.description {
+                        lines.push(Line::styled(format!("     {}", description), dim));
+                    }
+                }
+                if let Some(note) = note {
+                    lines.push(if note.is_empty() {
+                        Line::styled("     > type a note (optional)", dim)
+                    } else {
+                        Line::raw(format!("     > {}", note))
+                    });
+                }
+                lines.push(Line::raw(""));
+                let help = if checked.is_some() {
+                    "↑/↓ select · Space check · Enter confirm · Esc cancel"
+                } else {
+                    "↑/↓ select · Enter confirm · Esc cancel"
+                };
+                (format!(" {} ", title), vec![Span::styled(help, dim)])
+            }
+        };
+        lines.push(Line::from(buttons).centered());
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(title);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            popup,
+        );
+
+        if let (Kind::Question { answer }, Focus::Input) = (&self.kind, self.focus) {
+            let x = popup.x + 1 + 2 + answer.width() as u16;
+            let y = popup.y + 1 + text_height as u16 + 1;
+            if x < popup.right() - 1 && y < popup.bottom() - 1 {
+                frame.set_cursor(x, y);
+            }
+        }
+        if let Kind::Choice {
+            options,
+            selected,
+            note: Some(note),
+            ..
+        } = &self.kind
+        {
+            if *selected + 1 == options.len() {
+                let x = popup.x + 1 + 7 + note.width() as u16;
+                let y = popup.y + 1 + (text_height + 1 + option_rows(options)) as u16;
+                if x < popup.right() - 1 && y < popup.bottom() - 1 {
+                    frame.set_cursor(x, y);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! The multi-line input editor of the terminal console.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation as _;
+use unicode_width::UnicodeWidthStr as _;
+
+/// An editable text buffer with a cursor.
+///
+/// The cursor is a byte offset that always sits on a grapheme cluster boundary, so
+/// combined characters and emoji sequences are moved over and deleted as a whole.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Editor {
+    text: String,
+    cursor: usize,
+}
+
+/// The text of an editor wrapped to a given width, as it is drawn on the screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorLayout {
+    /// The screen rows, each fits into the width.
+    pub rows: Vec<String>,
+    /// The row and column of the cursor, in terminal cells.
+    pub cursor: (usize, usize),
+}
+
+fn is_word(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the cursor position as a byte offset into the text.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replaces the text and moves the cursor to its end.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// Returns the text and clears the editor.
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    /// Inserts text at the cursor, e.g. a typed character or pasted code.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.insert_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn insert_newline(&mut self) {
+        self.insert_char('\n');
+    }
+
+    fn prev_boundary(&self) -> Option<usize> {
+        self.text[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map(|(index, _)| index)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map(|grapheme| self.cursor + grapheme.len())
+    }
+
+    /// The byte range of the line holding `at`, without the line break.
+    fn line_bounds(&self, at: usize) -> (usize, usize) {
+        let start = self.text[..at].rfind('\n').map_or(0, |index| index + 1);
+        let end = self.text[at..]
+            .find('\n')
+            .map_or(self.text.len(), |index| at + index);
+        (start, end)
+    }
+
+    /// The byte offset of the line `start` closest to the display column `column`.
+    fn offset_at_column(&self, start: usize, column: usize) -> usize {
+        let (_, end) = self.line_bounds(start);
+        let mut width = 0;
+        for (index, grapheme) in self.text[start..end].grapheme_indices(true) {
+            width += grapheme.width();
+            if width > column {
+                return start + index;
+            }
+        }
+        end
+    }
+
+    pub fn move_left(&mut self) {
+        if let Some(index) = self.prev_boundary() {
+            self.cursor = index;
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if let Some(index) = self.next_boundary() {
+            self.cursor = index;
+        }
+    }
+
+    /// Moves to the start of the previous word.
+    pub fn move_word_left(&mut self) {
+        let mut iter = self.text[..self.cursor]
+            .grapheme_indices(true)
+            .rev()
+            .peekable();
+        while iter.next_if(|(_, g)| !is_word(g)).is_some() {}
+        let mut cursor = 0;
+        while let Some((index, _)) = iter.next_if(|(_, g)| is_word(g)) {
+            cursor = index;
+        }
+        self.cursor = cursor;
+    }
+
+    /// Moves to the end of the next word.
+    pub fn move_word_right(&mut self) {
+        let start = self.cursor;
+        let mut iter = self.text[start..].grapheme_indices(true).peekable();
+        while iter.next_if(|(_, g)| !is_word(g)).is_some() {}
+        while iter.next_if(|(_, g)| is_word(g)).is_some() {}
+        self.cursor = iter
+            .peek()
+            .map_or(self.text.len(), |(index, _)| start + index);
+    }
+
+    /// Moves to the start of the current line.
+    pub fn move_home(&mut self) {
+        self.cursor = self.line_bounds(self.cursor).0;
+    }
+
+    /// Moves to the end of the current line.
+    pub fn move_end(&mut self) {
+        self.cursor = self.line_bounds(self.cursor).1;
+    }
+
+    /// Moves to the previous line, returns `false` if the cursor is on the first line.
+    pub fn move_up(&mut self) -> bool {
+        let (start, _) = self.line_bounds(self.cursor);
+        if start == 0 {
+            return false;
+        }
+        let column = self.text[start..self.cursor].width();
+        let (previous, _) = self.line_bounds(start - 1);
+        self.cursor = self.offset_at_column(previous, column);
+        true
+    }
+
+    /// Moves to the next line, returns `false` if the cursor is on the last line.
+    pub fn move_down(&mut self) -> bool {
+        let (start, end) = self.line_bounds(self.cursor);
+        if end == self.text.len() {
+            return false;
+        }
+        let column = self.text[start..self.cursor].width();
+        self.cursor = self.offset_at_column(end + 1, column);
+        true
+    }
+
+    /// Deletes the grapheme before the cursor.
+    pub fn delete_backward(&mut self) {
+        if let Some(index) = self.prev_boundary() {
+            self.text.replace_range(index..self.cursor, "");
+            self.cursor = index;
+        }
+    }
+
+    /// Deletes the grapheme under the cursor.
+    pub fn delete_forward(&mut self) {
+        if let Some(index) = self.next_boundary() {
+            self.text.replace_range(self.cursor..index, "");
+        }
+    }
+
+    /// Deletes from the start of the previous word to the cursor.
+    pub fn delete_word_backward(&mut self) {
+        let end = self.cursor;
+        self.move_word_left();
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    /// Deletes from the cursor to the end of the next word.
+    pub fn delete_word_forward(&mut self) {
+        let start = self.cursor;
+        self.move_word_right();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Applies an editing key, returns `false` if the key is not an editing key.
+    ///
+    /// Enter is not handled here: plain Enter submits the input, Shift+Enter and
+    /// Alt+Enter insert a line break. Word motions use Ctrl or Alt with the arrows.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let word = key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Left if word => self.move_word_left(),
+            KeyCode::Right if word => self.move_word_right(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.move_home(),
+            KeyCode::End => self.move_end(),
+            KeyCode::Up => return self.move_up(),
+            KeyCode::Down => return self.move_down(),
+            KeyCode::Backspace if word => self.delete_word_backward(),
+            KeyCode::Backspace => self.delete_backward(),
+            KeyCode::Delete if word => self.delete_word_forward(),
+            KeyCode::Delete => self.delete_forward(),
+            KeyCode::Char('a') if ctrl => self.move_home(),
+            KeyCode::Char('e') if ctrl => self.move_end(),
+            KeyCode::Char('w') if ctrl => self.delete_word_backward(),
+            KeyCode::Char('j') if ctrl => self.insert_newline(),
+            KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.move_word_left()
+            }
+            KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.move_word_right()
+            }
+            KeyCode::Char(_) if word => return false,
+            KeyCode::Char(c) => self.insert_char(c),
+            KeyCode::Tab => self.insert_char('\t'),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Wraps the text into rows of at most `width` cells and locates the cursor.
+    ///
+    /// Tabs are shown as a single space so that the cell widths stay predictable.
+    pub fn layout(&self, width: usize) -> EditorLayout {
+        let width = width.max(2);
+        let mut rows = vec![String::new()];
+        let mut column = 0;
+        let mut cursor = None;
+        for (index, grapheme) in self.text.grapheme_indices(true) {
+            if index == self.cursor {
+                cursor = Some((rows.len() - 1, column));
+            }
+            if grapheme == "\n" || grapheme == "\r\n" {
+                rows.push(String::new());
+                column = 0;
+                continue;
+            }
+            let (grapheme, cells) = match grapheme {
+                "\t" => (" ", 1),
+                _ => (grapheme, grapheme.width()),
+            };
+            if column + cells > width && column > 0 {
+                rows.push(String::new());
+                column = 0;
+                if index == self.cursor {
+                    cursor = Some((rows.len() - 1, 0));
+                }
+            }
+            rows.last_mut().unwrap().push_str(grapheme);
+            column += cells;
+        }
+        let cursor = cursor.unwrap_or_else(|| {
+            if column >= width {
+                rows.push(String::new());
+                (rows.len() - 1, 0)
+            } else {
+                (rows.len() - 1, column)
+            }
+        });
+        EditorLayout { rows, cursor }
+    }
+}
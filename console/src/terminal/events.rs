@@ -0,0 +1,143 @@
+use crate::common::CancellationToken;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+/// How often the reader thread checks whether it has to stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub(super) fn is_ctrl_c(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// The token of the turn in flight, shared between the console and the event reader.
+///
+/// Interrupt keys are handled by the reader itself, so a turn can be cancelled while
+/// nobody is waiting for input, e.g. during a long LLM request or tool run.
+///
+/// While a modal dialog is open, Esc belongs to the dialog and only Ctrl-C cancels the turn.
+#[derive(Clone, Default)]
+pub(super) struct Interrupt {
+    current: Arc<Mutex<Option<CancellationToken>>>,
+    modal: Arc<AtomicBool>,
+}
+
+impl Interrupt {
+    pub(super) fn set(&self, token: Option<CancellationToken>) {
+        *self.current.lock().unwrap() = token;
+    }
+
+    pub(super) fn set_modal(&self, modal: bool) {
+        self.modal.store(modal, Ordering::Relaxed);
+    }
+
+    /// Cancels the turn in flight on Ctrl-C or Esc, returns `true` if the event was consumed.
+    pub(super) fn intercept(&self, event: &Event) -> bool {
+        let Event::Key(key) = event else {
+            return false;
+        };
+        let esc = key.code == KeyCode::Esc && !self.modal.load(Ordering::Relaxed);
+        if key.kind != KeyEventKind::Press || !(is_ctrl_c(key) || esc) {
+            return false;
+        }
+        match self.current.lock().unwrap().as_ref() {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A source of terminal events for the `Terminal` console.
+///
+/// The events are read on a dedicated thread, so a source may block.
+pub trait EventSource: Send + 'static {
+    /// Waits up to `timeout` for the next event, returns `None` if there was none.
+    ///
+    /// An error ends the reading, the console then fails with `ConsoleError::Terminated`.
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>>;
+}
+
+/// The events of the real terminal, read with `crossterm::event`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CrosstermEvents;
+
+impl EventSource for CrosstermEvents {
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        if event::poll(timeout)? {
+            event::read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Scripted events, e.g. key presses sent by a test, the source ends when all the senders
+/// are dropped.
+impl EventSource for Receiver<Event> {
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        match self.recv_timeout(timeout) {
+            Ok(event) => Ok(Some(event)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "no more events",
+            )),
+        }
+    }
+}
+
+/// Reads terminal events on a dedicated thread and forwards them to the console.
+///
+/// The thread stops when the reader is dropped or the receiving side is closed.
+pub(super) struct EventReader {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl EventReader {
+    pub(super) fn spawn(
+        interrupt: Interrupt,
+        mut source: impl EventSource,
+    ) -> (Self, UnboundedReceiver<Event>) {
+        let (sender, receiver) = unbounded_channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = std::thread::spawn({
+            let stop = stop.clone();
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match source.poll_event(POLL_INTERVAL) {
+                        Ok(Some(event)) => {
+                            if !interrupt.intercept(&event) && sender.send(event).is_err() {
+                                break;
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(_) => break,
+                    }
+                }
+            }
+        });
+        let reader = Self {
+            stop,
+            thread: Some(thread),
+        };
+        (reader, receiver)
+    }
+}
+
+impl Drop for EventReader {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
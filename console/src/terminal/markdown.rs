@@ -0,0 +1,466 @@
+//! Renders the markdown of model answers into styled transcript lines.
+
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use std::sync::LazyLock;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+use unicode_width::UnicodeWidthStr as _;
+
+/// The syntect theme used for code blocks.
+const CODE_THEME: &str = "base16-ocean.dark";
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEME: LazyLock<Theme> = LazyLock::new(|| {
+    let mut themes = ThemeSet::load_defaults();
+    themes.themes.remove(CODE_THEME).unwrap_or_default()
+});
+
+fn dim() -> Style {
+    Style::default().fg(Color::DarkGray)
+}
+
+/// A container block whose prefix is repeated on every line it holds.
+enum Container {
+    Quote,
+    /// a list with the number of the next item, `None` for bullet lists
+    List(Option<u64>),
+    /// a list item, the marker is written on its first line only
+    Item {
+        marker: Option<String>,
+        width: usize,
+    },
+}
+
+#[derive(Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    head_rows: usize,
+}
+
+#[derive(Default)]
+struct Renderer {
+    lines: Vec<Line<'static>>,
+    spans: Vec<Span<'static>>,
+    styles: Vec<Style>,
+    containers: Vec<Container>,
+    /// the language and text of the fenced code block being read
+    code: Option<(String, String)>,
+    table: Option<Table>,
+    link: Option<String>,
+}
+
+impl Renderer {
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(self.style().patch(style));
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let style = self.style();
+        match &mut self.table {
+            Some(table) => {
+                if let Some(cell) = table.rows.last_mut().and_then(|row| row.last_mut()) {
+                    cell.push(Span::styled(text.to_string(), style));
+                }
+            }
+            None => self.spans.push(Span::styled(text.to_string(), style)),
+        }
+    }
+
+    /// The prefix of the next line: quote bars, list indentation and the item marker.
+    fn prefix(&mut self) -> Vec<Span<'static>> {
+        let mut prefix = Vec::new();
+        for container in &mut self.containers {
+            match container {
+                Container::Quote => prefix.push(Span::styled("│ ", dim())),
+                Container::List(_) => {}
+                Container::Item { marker, width } => match marker.take() {
+                    Some(marker) => {
+                        prefix.push(Span::styled(marker, Style::default().fg(Color::Cyan)))
+                    }
+                    None => prefix.push(Span::raw(" ".repeat(*width))),
+                },
+            }
+        }
+        prefix
+    }
+
+    fn push_line(&mut self, spans: Vec<Span<'static>>) {
+        let mut line = self.prefix();
+        line.extend(spans);
+        self.lines.push(Line::from(line));
+    }
+
+    /// Ends the current line, if anything was written on it.
+    fn flush(&mut self) {
+        if !self.spans.is_empty() {
+            let spans = std::mem::take(&mut self.spans);
+            self.push_line(spans);
+        }
+    }
+
+    /// Ends a block, top-level blocks are separated by a blank line.
+    fn end_block(&mut self) {
+        self.flush();
+        if self.containers.is_empty() {
+            self.lines.push(Line::default());
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush();
+                let style = match level {
+                    HeadingLevel::H1 => Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    HeadingLevel::H2 => Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD),
+                    _ => Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                };
+                self.push_style(style);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.containers.push(Container::Quote);
+                self.push_style(Style::default().add_modifier(Modifier::ITALIC));
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.containers.push(Container::List(start));
+            }
+            Tag::Item => {
+                self.flush();
+                let marker = match self.containers.last_mut() {
+                    Some(Container::List(Some(number))) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                let width = marker.width();
+                self.containers.push(Container::Item {
+                    marker: Some(marker),
+                    width,
+                });
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((language, String::new()));
+            }
+            Tag::Emphasis => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => {
+                self.push_style(Style::default().add_modifier(Modifier::CROSSED_OUT))
+            }
+            Tag::Link { dest_url, .. } => {
+                self.link = Some(dest_url.to_string());
+                self.push_style(
+                    Style::default()
+                        .fg(Color::Blue)
+                        .add_modifier(Modifier::UNDERLINED),
+                );
+            }
+            Tag::Table(alignments) => {
+                self.flush();
+                self.table = Some(Table {
+                    alignments,
+                    ..Table::default()
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+                    row.push(Vec::new());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.end_block(),
+            TagEnd::Heading(_) => {
+                self.styles.pop();
+                self.end_block();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.styles.pop();
+                self.flush();
+                self.containers.pop();
+                if self.containers.is_empty() {
+                    self.lines.push(Line::default());
+                }
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.containers.pop();
+                if self.containers.is_empty() {
+                    self.lines.push(Line::default());
+                }
+            }
+            TagEnd::Item => {
+                self.flush();
+                self.containers.pop();
+            }
+            TagEnd::CodeBlock => {
+                if let Some((language, code)) = self.code.take() {
+                    self.code_block(&language, &code);
+                }
+                self.end_block();
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.styles.pop();
+            }
+            TagEnd::Link => {
+                self.styles.pop();
+                if let Some(url) = self.link.take() {
+                    let text: String = self
+                        .spans
+                        .iter()
+                        .map(|span| span.content.as_ref())
+                        .collect();
+                    if !text.ends_with(&url) {
+                        self.spans.push(Span::styled(format!(" <{}>", url), dim()));
+                    }
+                }
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.head_rows = table.rows.len();
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.render_table(table);
+                }
+                self.end_block();
+            }
+            _ => {}
+        }
+    }
+
+    /// Writes a code block between its fences, highlighted by language.
+    fn code_block(&mut self, language: &str, code: &str) {
+        self.push_line(vec![Span::styled(format!("```{}", language), dim())]);
+        let syntax = SYNTAXES
+            .find_syntax_by_token(language)
+            .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+        let mut highlighter = HighlightLines::new(syntax, &THEME);
+        for line in LinesWithEndings::from(code) {
+            let spans = match highlighter.highlight_line(line, &SYNTAXES) {
+                Ok(regions) => regions
+                    .into_iter()
+                    .map(|(style, text)| {
+                        let color = style.foreground;
+                        Span::styled(
+                            text.trim_end_matches(['\n', '\r']).to_string(),
+                            Style::default().fg(Color::Rgb(color.r, color.g, color.b)),
+                        )
+                    })
+                    .collect(),
+                Err(_) => vec![Span::raw(line.trim_end_matches(['\n', '\r']).to_string())],
+            };
+            self.push_line(spans);
+        }
+        self.push_line(vec![Span::styled("```", dim())]);
+    }
+
+    /// Writes a table with its columns padded to the widest cell.
+    fn render_table(&mut self, table: Table) {
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let cell_width = |cell: &Vec<Span>| cell.iter().map(|span| span.width()).sum::<usize>();
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                table
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .map(cell_width)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for (index, row) in table.rows.into_iter().enumerate() {
+            let head = index < table.head_rows;
+            let mut spans = Vec::new();
+            for (column, width) in widths.iter().enumerate() {
+                if column > 0 {
+                    spans.push(Span::styled(" │ ", dim()));
+                }
+                let cell = row.get(column).cloned().unwrap_or_default();
+                let padding = width - cell_width(&cell);
+                let (before, after) = match table.alignments.get(column) {
+                    Some(Alignment::Right) => (padding, 0),
+                    Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+                spans.push(Span::raw(" ".repeat(before)));
+                spans.extend(cell.into_iter().map(|span| {
+                    if head {
+                        span.patch_style(Style::default().add_modifier(Modifier::BOLD))
+                    } else {
+                        span
+                    }
+                }));
+                spans.push(Span::raw(" ".repeat(after)));
+            }
+            self.push_line(spans);
+            if head && index + 1 == table.head_rows {
+                let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+                self.push_line(vec![Span::styled(rule.join("─┼─"), dim())]);
+            }
+        }
+    }
+}
+
+/// Renders a markdown text into lines styled for the terminal.
+///
+/// Headings, emphasis, lists, block quotes, tables and links are styled, fenced code
+/// blocks are highlighted according to their language. The rendering tolerates an
+/// incomplete text, so it can be applied to an answer while it streams in: an unclosed
+/// code fence is shown as a code block up to the end of the text.
+pub fn render(text: &str) -> Vec<Line<'static>> {
+    let mut lines = render_blocks(text);
+    while lines.last().is_some_and(|line| line.width() == 0) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Renders whole blocks, keeping the blank line that ends the last one.
+fn render_blocks(text: &str) -> Vec<Line<'static>> {
+    let mut renderer = Renderer::default();
+    for event in Parser::new_ext(text, options()) {
+        match event {
+            Event::Start(tag) => renderer.start(tag),
+            Event::End(tag) => renderer.end(tag),
+            Event::Text(text) => match &mut renderer.code {
+                Some((_, code)) => code.push_str(&text),
+                None => renderer.push_text(&text),
+            },
+            Event::Code(code) => {
+                renderer.push_style(Style::default().fg(Color::Yellow));
+                renderer.push_text(&code);
+                renderer.styles.pop();
+            }
+            Event::SoftBreak => renderer.push_text(" "),
+            Event::HardBreak => renderer.flush(),
+            Event::Rule => {
+                renderer.flush();
+                renderer.push_line(vec![Span::styled("─".repeat(20), dim())]);
+                renderer.end_block();
+            }
+            Event::TaskListMarker(checked) => {
+                renderer.push_text(if checked { "[x] " } else { "[ ] " })
+            }
+            Event::Html(html) => {
+                for line in html.lines() {
+                    renderer.push_text(line);
+                    renderer.flush();
+                }
+            }
+            Event::InlineHtml(html) => renderer.push_text(&html),
+            _ => {}
+        }
+    }
+    renderer.flush();
+    renderer.lines
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+/// How much of a growing markdown text is rendered for good.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Progress {
+    /// the length of the finished blocks in the text
+    text: usize,
+    /// the number of lines the finished blocks are rendered as
+    lines: usize,
+}
+
+/// Renders a markdown text that has grown since the last call into `lines`, which hold the
+/// lines of the last call, and returns how many of them are left unchanged.
+///
+/// The top-level blocks before the last two are finished, more text can't change them, so
+/// they are rendered only once. The last two are rendered again on every call, the start
+/// of the last one may still turn out to continue the one before, like `2` after a list
+/// item `1. one`. The lines are the ones of `render`, except that a link is not resolved
+/// by a reference defined in another block.
+pub fn render_growing(
+    text: &str,
+    lines: &mut Vec<Line<'static>>,
+    progress: &mut Progress,
+) -> usize {
+    let kept = progress.lines;
+    lines.truncate(kept);
+    let rest = &text[progress.text..];
+    let open = open_blocks_start(rest);
+    if open > 0 {
+        lines.extend(render_blocks(&rest[..open]));
+        progress.text += open;
+        progress.lines = lines.len();
+    }
+    let tail = render(&rest[open..]);
+    if tail.is_empty() {
+        // nothing is drawn after the finished blocks yet, so they lose their blank line
+        while lines.last().is_some_and(|line| line.width() == 0) {
+            lines.pop();
+        }
+        if lines.len() < progress.lines {
+            *progress = Progress::default();
+        }
+    }
+    lines.extend(tail);
+    kept.min(lines.len())
+}
+
+/// Returns the offset of the second to last top-level block of a text, 0 if there are not
+/// more than two.
+fn open_blocks_start(text: &str) -> usize {
+    let mut depth = 0usize;
+    let (mut previous, mut last) = (0, 0);
+    for (event, range) in Parser::new_ext(text, options()).into_offset_iter() {
+        let top_level = depth == 0;
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if top_level && !matches!(event, Event::End(_)) {
+            (previous, last) = (last, range.start);
+        }
+    }
+    previous
+}
@@ -0,0 +1,208 @@
+//! The diff review viewer of the terminal console.
+
+use super::diff_style;
+use crate::diff::{FileDiff, accepted_hunks};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Margin, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+const HELP: &str = " y accept · n reject · a/d rest of file · ←/→ hunk · ↑/↓ scroll · Enter done · Esc reject all ";
+
+/// A full-screen viewer to accept or reject the hunks of a multi-file diff.
+///
+/// The selected hunk is accepted with `y` or rejected with `n`, which moves to the next
+/// one. `a` and `d` decide the selected hunk and the later ones of its file, like in
+/// `StdIo`, and move to the next file. Enter ends the review, hunks left undecided are
+/// rejected, and Esc rejects everything.
+#[derive(Debug, Clone)]
+pub struct Review {
+    files: Vec<FileDiff>,
+    /// the decision on every hunk of every file, `None` while undecided
+    decisions: Vec<Vec<Option<bool>>>,
+    /// the file and hunk index of every hunk, in display order
+    hunks: Vec<(usize, usize)>,
+    /// the index of the selected hunk in `hunks`
+    current: usize,
+    /// the first row shown
+    top: usize,
+    /// whether the next render scrolls to the selected hunk
+    show_current: bool,
+    /// the number of rows shown by the last render
+    height: usize,
+}
+
+impl Review {
+    pub fn new(files: Vec<FileDiff>) -> Self {
+        let decisions = files
+            .iter()
+            .map(|file| vec![None; file.hunks.len()])
+            .collect();
+        let hunks = files
+            .iter()
+            .enumerate()
+            .flat_map(|(file, diff)| (0..diff.hunks.len()).map(move |hunk| (file, hunk)))
+            .collect();
+        Self {
+            files,
+            decisions,
+            hunks,
+            current: 0,
+            top: 0,
+            show_current: true,
+            height: 0,
+        }
+    }
+
+    /// Returns the decisions taken so far, by file and hunk.
+    pub fn decisions(&self) -> &[Vec<Option<bool>>] {
+        &self.decisions
+    }
+
+    fn decide(&mut self, accept: bool) {
+        if let Some(&(file, hunk)) = self.hunks.get(self.current) {
+            self.decisions[file][hunk] = Some(accept);
+            self.select(self.current + 1);
+        }
+    }
+
+    fn decide_rest_of_file(&mut self, accept: bool) {
+        if let Some(&(file, hunk)) = self.hunks.get(self.current) {
+            let rest = &mut self.decisions[file][hunk..];
+            rest.fill(Some(accept));
+            let next = self.current + rest.len();
+            self.select(next);
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.current = index.min(self.hunks.len().saturating_sub(1));
+        self.show_current = true;
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.top = self.top.saturating_sub(rows);
+        self.show_current = false;
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.top += rows;
+        self.show_current = false;
+    }
+
+    /// Returns the accepted changes, undecided hunks are rejected.
+    fn finish(&self) -> Vec<FileDiff> {
+        let accepted: Vec<Vec<bool>> = self
+            .decisions
+            .iter()
+            .map(|decisions| decisions.iter().map(|d| *d == Some(true)).collect())
+            .collect();
+        accepted_hunks(self.files.clone(), &accepted)
+    }
+
+    /// Handles a key press, returns the accepted changes once the review is over.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Vec<FileDiff>> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => self.decide(true),
+            KeyCode::Char('n') | KeyCode::Char('N') => self.decide(false),
+            KeyCode::Char('a') | KeyCode::Char('A') => self.decide_rest_of_file(true),
+            KeyCode::Char('d') | KeyCode::Char('D') => self.decide_rest_of_file(false),
+            KeyCode::Right | KeyCode::Tab | KeyCode::Char('j') => self.select(self.current + 1),
+            KeyCode::Left | KeyCode::BackTab | KeyCode::Char('k') => {
+                self.select(self.current.saturating_sub(1))
+            }
+            KeyCode::Up => self.scroll_up(1),
+            KeyCode::Down => self.scroll_down(1),
+            KeyCode::PageUp => self.scroll_up(self.height.saturating_sub(1).max(1)),
+            KeyCode::PageDown => self.scroll_down(self.height.saturating_sub(1).max(1)),
+            KeyCode::Enter => return Some(self.finish()),
+            KeyCode::Esc => return Some(Vec::new()),
+            _ => {}
+        }
+        None
+    }
+
+    /// Returns the lines of the diff and the row of the header of the selected hunk.
+    fn lines(&self) -> (Vec<Line<'static>>, usize) {
+        let mut lines = Vec::new();
+        let mut current_row = 0;
+        for (file_index, file) in self.files.iter().enumerate() {
+            if file_index > 0 {
+                lines.push(Line::raw(""));
+            }
+            lines.push(Line::styled(
+                file.path.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+            for (hunk_index, hunk) in file.hunks.iter().enumerate() {
+                let selected = self.hunks.get(self.current) == Some(&(file_index, hunk_index));
+                if selected {
+                    current_row = lines.len();
+                }
+                let (marker, color) = match self.decisions[file_index][hunk_index] {
+                    Some(true) => ("[✓]", Color::Green),
+                    Some(false) => ("[✗]", Color::Red),
+                    None => ("[ ]", Color::Reset),
+                };
+                let mut marker_style = Style::default().fg(color);
+                if selected {
+                    marker_style = marker_style.add_modifier(Modifier::REVERSED);
+                }
+                lines.push(Line::from(vec![
+                    Span::raw(if selected { "▶ " } else { "  " }),
+                    Span::styled(marker, marker_style),
+                    Span::raw(" "),
+                    Span::styled(hunk.header.clone(), Style::default().fg(Color::Cyan)),
+                ]));
+                lines.extend(
+                    hunk.lines
+                        .iter()
+                        .map(|line| Line::styled(format!("      {}", line), diff_style(line))),
+                );
+            }
+        }
+        (lines, current_row)
+    }
+
+    /// Draws the viewer over `area`, keeping the selected hunk visible after it changed.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let popup = area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+        let (lines, current_row) = self.lines();
+        self.height = popup.height.saturating_sub(2) as usize;
+        if self.show_current && (current_row < self.top || current_row >= self.top + self.height) {
+            self.top = current_row;
+        }
+        self.top = self.top.min(lines.len().saturating_sub(self.height));
+
+        let decided = self
+            .decisions
+            .iter()
+            .flatten()
+            .filter(|d| d.is_some())
+            .count();
+        let title = format!(
+            " Review changes: hunk {}/{}, {} decided ",
+            (self.current + 1).min(self.hunks.len()),
+            self.hunks.len(),
+            decided
+        );
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(title)
+            .title_bottom(Line::styled(
+                HELP,
+                Style::default().add_modifier(Modifier::DIM),
+            ));
+        let visible: Vec<Line> = lines.into_iter().skip(self.top).take(self.height).collect();
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(visible).block(block), popup);
+    }
+}
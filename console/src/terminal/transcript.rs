@@ -0,0 +1,210 @@
+//! The scrollable transcript pane of the terminal console.
+
+use ratatui::text::{Line, Span};
+use unicode_segmentation::UnicodeSegmentation as _;
+use unicode_width::UnicodeWidthStr as _;
+
+/// Tabs are expanded to this many spaces before wrapping.
+const TAB_WIDTH: usize = 4;
+
+/// Wraps a text to rows of at most `width` cells.
+///
+/// Lines break between words, words longer than the width are split. Explicit line
+/// breaks are kept, an empty text is a single empty row.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    text.split('\n')
+        .flat_map(|line| wrap_line(&Line::raw(line.to_string()), width))
+        .map(|row| row.spans.iter().map(|span| span.content.as_ref()).collect())
+        .collect()
+}
+
+/// A row being filled by `wrap_line`.
+#[derive(Default)]
+struct Row {
+    spans: Vec<Span<'static>>,
+    width: usize,
+}
+
+impl Row {
+    fn push(&mut self, span: Span<'static>, width: usize) {
+        match self.spans.last_mut() {
+            Some(last) if last.style == span.style => last.content.to_mut().push_str(&span.content),
+            _ => self.spans.push(span),
+        }
+        self.width += width;
+    }
+
+    /// Returns the line, without the trailing blanks.
+    fn finish(mut self) -> Line<'static> {
+        while let Some(last) = self.spans.last_mut() {
+            let trimmed = last.content.trim_end().len();
+            if trimmed > 0 {
+                last.content.to_mut().truncate(trimmed);
+                break;
+            }
+            self.spans.pop();
+        }
+        Line::from(self.spans)
+    }
+}
+
+/// Wraps a styled line to rows of at most `width` cells, keeping the span styles.
+pub fn wrap_line(line: &Line<'static>, width: usize) -> Vec<Line<'static>> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut row = Row::default();
+    for span in &line.spans {
+        let content = span.content.replace('\t', &" ".repeat(TAB_WIDTH));
+        for piece in content.split_word_bounds() {
+            let piece_width = piece.width();
+            if row.width + piece_width <= width {
+                row.push(Span::styled(piece.to_string(), span.style), piece_width);
+                continue;
+            }
+            if row.width > 0 {
+                rows.push(std::mem::take(&mut row).finish());
+            }
+            if piece.trim().is_empty() {
+                continue;
+            }
+            for grapheme in piece.graphemes(true) {
+                let grapheme_width = grapheme.width();
+                if row.width + grapheme_width > width && row.width > 0 {
+                    rows.push(Line::from(std::mem::take(&mut row).spans));
+                }
+                row.push(
+                    Span::styled(grapheme.to_string(), span.style),
+                    grapheme_width,
+                );
+            }
+        }
+    }
+    rows.push(Line::from(row.spans));
+    rows
+}
+
+/// The scroll position of the transcript.
+///
+/// By default the view follows the tail, so new output is always visible. Scrolling up
+/// anchors the view to a row of a message, which keeps the same text on screen when new
+/// output arrives or the pane is resized, until the user scrolls back to the bottom.
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    /// the message index and the row within the message shown at the top, `None` follows
+    /// the tail
+    anchor: Option<(usize, usize)>,
+    /// whether output arrived while the view was not following the tail
+    unseen: bool,
+    /// the message and row of every row of the last rendered layout
+    rows: Vec<(usize, usize)>,
+    /// the index of the top row and the height of the last rendered view
+    top: usize,
+    height: usize,
+}
+
+impl Transcript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the view shows the end of the transcript.
+    pub fn is_following(&self) -> bool {
+        self.anchor.is_none()
+    }
+
+    /// Returns `true` if output arrived below the view since the user scrolled up.
+    pub fn has_unseen_output(&self) -> bool {
+        self.unseen
+    }
+
+    /// Records that the transcript has changed.
+    pub fn output_added(&mut self) {
+        if self.anchor.is_some() {
+            self.unseen = true;
+        }
+    }
+
+    /// Goes back to following the tail.
+    pub fn follow(&mut self) {
+        self.anchor = None;
+        self.unseen = false;
+    }
+
+    /// Scrolls towards the start of the transcript.
+    pub fn scroll_up(&mut self, rows: usize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let top = self.top.saturating_sub(rows);
+        self.anchor = Some(self.rows[top]);
+    }
+
+    /// Scrolls towards the end of the transcript, following the tail once it is reached.
+    pub fn scroll_down(&mut self, rows: usize) {
+        let top = self.top + rows;
+        if top + self.height >= self.rows.len() {
+            self.follow();
+        } else {
+            self.anchor = Some(self.rows[top]);
+        }
+    }
+
+    /// Scrolls up by the height of the view, keeping one row of context.
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.height.saturating_sub(1).max(1));
+    }
+
+    /// Scrolls down by the height of the view, keeping one row of context.
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.height.saturating_sub(1).max(1));
+    }
+
+    /// Lays the messages out for a view of `width` by `height` cells and returns the
+    /// visible rows. Each message is given as its lines, before wrapping.
+    pub fn view<'a>(
+        &mut self,
+        messages: impl IntoIterator<Item = &'a [Line<'static>]>,
+        width: usize,
+        height: usize,
+    ) -> Vec<Line<'static>> {
+        let wrapped: Vec<Vec<Line<'static>>> = messages
+            .into_iter()
+            .map(|message| {
+                message
+                    .iter()
+                    .flat_map(|line| wrap_line(line, width))
+                    .collect()
+            })
+            .collect();
+        self.view_rows(wrapped.iter().map(Vec::as_slice), height)
+    }
+
+    /// Like `view`, for messages already wrapped to the width of the view.
+    pub fn view_rows<'a>(
+        &mut self,
+        messages: impl IntoIterator<Item = &'a [Line<'static>]>,
+        height: usize,
+    ) -> Vec<Line<'static>> {
+        let mut keys = Vec::new();
+        let mut rows = Vec::new();
+        for (index, message) in messages.into_iter().enumerate() {
+            for (row_index, row) in message.iter().enumerate() {
+                keys.push((index, row_index));
+                rows.push(row);
+            }
+        }
+        let bottom = rows.len().saturating_sub(height);
+        let top = match self.anchor {
+            None => bottom,
+            // a reflow can leave fewer rows in the anchored message, the closest one is used
+            Some(anchor) => keys.partition_point(|key| *key < anchor).min(bottom),
+        };
+        if top == bottom {
+            self.follow();
+        }
+        self.rows = keys;
+        self.top = top;
+        self.height = height;
+        rows.into_iter().skip(top).take(height).cloned().collect()
+    }
+}
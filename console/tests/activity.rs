@@ -0,0 +1,53 @@
+use console::common::ToolStatus;
+use console::terminal::activity::Activity;
+use ratatui::text::Line;
+use std::time::Duration;
+
+fn text(lines: &[Line]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_running() {
+    let activity = Activity::new("run".to_string(), r#"{"cmd":"cargo test"}"#.to_string());
+    assert!(activity.is_running());
+    let lines = text(&activity.render("one\ntwo\nthree\nfour\n"));
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].contains(r#"run({"cmd":"cargo test"})"#));
+    // only the tail of the output is shown
+    assert_eq!(lines[1..], ["  two", "  three", "  four"]);
+}
+
+#[test]
+fn test_finished() {
+    let mut activity = Activity::new("run".to_string(), "{}".to_string());
+    activity.finish(ToolStatus::Succeeded, Duration::from_millis(1500));
+    assert!(!activity.is_running());
+    assert_eq!(
+        text(&activity.render("one\ntwo\n")),
+        ["✓ run({}) · 1.5s · 2 lines (Ctrl-O to expand)"]
+    );
+
+    activity.set_expanded(true);
+    assert_eq!(
+        text(&activity.render("one\ntwo\n")),
+        ["✓ run({}) · 1.5s", "  one", "  two"]
+    );
+
+    activity.finish(
+        ToolStatus::Failed("exit status 101".to_string()),
+        Duration::ZERO,
+    );
+    assert_eq!(
+        text(&activity.render("")),
+        ["✗ run({}) · 0.0s · exit status 101"]
+    );
+}
@@ -0,0 +1,77 @@
+use console::command::{ArgSpec, CommandRegistry, CommandSpec};
+use console::common::{ConsoleError, ConsoleInput};
+
+#[test]
+fn test_builtin_commands() {
+    let registry = CommandRegistry::default();
+    assert_eq!(registry.parse("/exit").unwrap(), ConsoleInput::Exit);
+    assert_eq!(registry.parse("/help").unwrap(), ConsoleInput::Help);
+    assert_eq!(registry.parse("/thinking on").unwrap(), ConsoleInput::Thinking(true));
+    assert_eq!(
+        registry.parse("just a prompt").unwrap(),
+        ConsoleInput::Prompt { prompt: "just a prompt".to_string() }
+    );
+}
+
+#[test]
+fn test_registered_command() {
+    let mut registry = CommandRegistry::default();
+    registry.register(
+        CommandSpec::new("model", "Switch the model")
+            .arg(ArgSpec::required("name"))
+            .arg(ArgSpec::optional("provider")),
+    );
+
+    assert_eq!(
+        registry.parse("/model 'gpt 4'").unwrap(),
+        ConsoleInput::Command { name: "model".to_string(), args: vec!["gpt 4".to_string()] }
+    );
+    assert_eq!(
+        registry.parse("/model gpt-4o openai").unwrap(),
+        ConsoleInput::Command {
+            name: "model".to_string(),
+            args: vec!["gpt-4o".to_string(), "openai".to_string()]
+        }
+    );
+
+    // Missing and extra arguments are rejected
+    for line in ["/model", "/model a b c"] {
+        match registry.parse(line) {
+            Err(ConsoleError::UnknownCommand { command }) => assert_eq!(command, "/model"),
+            other => panic!("Expected UnknownCommand error, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_handler_and_rest_arguments() {
+    let mut registry = CommandRegistry::empty();
+    registry.register(
+        CommandSpec::new("/say", "Send the words as a prompt")
+            .arg(ArgSpec::rest("words"))
+            .handler(|args| ConsoleInput::Prompt { prompt: args.join(" ") }),
+    );
+
+    assert_eq!(
+        registry.parse("/say hello  world").unwrap(),
+        ConsoleInput::Prompt { prompt: "hello world".to_string() }
+    );
+    assert_eq!(registry.parse("/say").unwrap(), ConsoleInput::Prompt { prompt: String::new() });
+    // The built-in commands are not part of an empty registry
+    assert!(registry.parse("/exit").is_err());
+}
+
+#[test]
+fn test_help() {
+    let mut registry = CommandRegistry::default();
+    registry.register(CommandSpec::new("review", "Review a file").arg(ArgSpec::required("path")));
+    let help = registry.help();
+
+    let review = help.lines().find(|line| line.contains("/review <path>")).unwrap();
+    assert!(review.ends_with("  Review a file"));
+    assert!(help.contains("/thinking on|off"));
+    assert!(help.contains("/help"));
+
+    registry.unregister("/review");
+    assert!(!registry.help().contains("/review"));
+}
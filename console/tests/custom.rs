@@ -0,0 +1,109 @@
+use console::command::CommandRegistry;
+use console::common::ConsoleInput;
+use console::custom::{self, PromptCommand};
+use std::path::PathBuf;
+
+#[test]
+fn test_parse_front_matter() {
+    let command = PromptCommand::parse(
+        "review",
+        "---\ndescription: Review a file\n---\nReview $ARGUMENTS carefully.\n",
+        PathBuf::from("review.md"),
+    );
+    assert_eq!(command.description, "Review a file");
+    assert_eq!(command.template, "Review $ARGUMENTS carefully.");
+}
+
+#[test]
+fn test_parse_without_front_matter() {
+    let command = PromptCommand::parse(
+        "changelog",
+        "# Write the changelog\n\nSummarize the commits since the last tag.",
+        PathBuf::from("changelog.md"),
+    );
+    assert_eq!(command.description, "Write the changelog");
+    assert!(command.template.starts_with("# Write the changelog"));
+}
+
+#[test]
+fn test_expand() {
+    let review = PromptCommand::parse("review", "Review $ARGUMENTS.", PathBuf::new());
+    assert_eq!(
+        review.expand(&["src/lib.rs".to_string(), "src/main.rs".to_string()]),
+        "Review src/lib.rs src/main.rs."
+    );
+
+    let tests = PromptCommand::parse("tests", "Write tests.", PathBuf::new());
+    assert_eq!(tests.expand(&[]), "Write tests.");
+    assert_eq!(tests.expand(&["for parser".to_string()]), "Write tests.\n\nfor parser");
+}
+
+#[test]
+fn test_register_dirs() {
+    let user = tempfile::tempdir().unwrap();
+    let project = tempfile::tempdir().unwrap();
+    std::fs::write(user.path().join("review.md"), "User review of $ARGUMENTS").unwrap();
+    std::fs::write(user.path().join("tests.md"), "Write tests for $ARGUMENTS").unwrap();
+    std::fs::write(project.path().join("review.md"), "Project review of $ARGUMENTS").unwrap();
+    std::fs::write(project.path().join("exit.md"), "Shadowing a built-in").unwrap();
+    std::fs::write(project.path().join("notes.txt"), "Not a command").unwrap();
+
+    let mut registry = CommandRegistry::default();
+    let dirs = vec![
+        user.path().to_path_buf(),
+        project.path().to_path_buf(),
+        project.path().join("missing"),
+    ];
+    let (registered, warnings) = custom::register_dirs(&mut registry, &dirs);
+    assert!(warnings.is_empty());
+    let names: Vec<&str> = registered.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["review", "tests"]);
+
+    // The project command wins over the user command
+    assert_eq!(
+        registry.parse("/review src/lib.rs").unwrap(),
+        ConsoleInput::Prompt { prompt: "Project review of src/lib.rs".to_string() }
+    );
+    assert_eq!(
+        registry.parse("/tests").unwrap(),
+        ConsoleInput::Prompt { prompt: "Write tests for ".to_string() }
+    );
+    // Built-in commands are not replaced
+    assert_eq!(registry.parse("/exit").unwrap(), ConsoleInput::Exit);
+    assert!(registry.help().contains("/review [arguments...]"));
+}
+
+#[test]
+fn test_parse_windows_line_endings() {
+    let command = PromptCommand::parse(
+        "review",
+        "---\r\ndescription: Review a file\r\n---\r\nReview $ARGUMENTS.\r\nBe brief.\r\n",
+        PathBuf::from("review.md"),
+    );
+    assert_eq!(command.description, "Review a file");
+    assert_eq!(command.template, "Review $ARGUMENTS.\nBe brief.");
+}
+
+#[test]
+fn test_broken_files_are_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("review.md"), "Review $ARGUMENTS").unwrap();
+    std::fs::write(dir.path().join("binary.md"), [0xff, 0xfe, 0x00]).unwrap();
+
+    let mut registry = CommandRegistry::default();
+    let (registered, warnings) = custom::register_dirs(&mut registry, &[dir.path().to_path_buf()]);
+    assert_eq!(registered.len(), 1);
+    assert_eq!(registered[0].name, "review");
+    assert_eq!(warnings.len(), 1);
+    assert!(
+        warnings[0].starts_with("Skipped the command file") && warnings[0].contains("binary.md"),
+        "{}",
+        warnings[0]
+    );
+}
+
+#[test]
+fn test_default_dirs() {
+    let dirs = custom::default_dirs(&PathBuf::from("/work/project"));
+    assert_eq!(dirs.last(), Some(&PathBuf::from("/work/project/.partner/commands")));
+}
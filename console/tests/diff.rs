@@ -0,0 +1,79 @@
+use console::diff::{FileDiff, Hunk, accepted_hunks};
+
+const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 1234567..89abcde 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,2 +1,2 @@
+-old
++new
+ same
+@@ -10 +10 @@
+-ten
++TEN
+--- /dev/null
++++ b/README.md
+@@ -0,0 +1 @@
++# Title
+";
+
+#[test]
+fn test_parse() {
+    let files = FileDiff::parse(DIFF);
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].path, "src/lib.rs");
+    assert_eq!(files[0].hunks.len(), 2);
+    assert_eq!(
+        files[0].hunks[0],
+        Hunk {
+            header: "@@ -1,2 +1,2 @@".to_string(),
+            lines: vec!["-old".to_string(), "+new".to_string(), " same".to_string()],
+        }
+    );
+    assert_eq!(files[1].path, "README.md");
+    assert_eq!(
+        files[1].to_string(),
+        "--- /dev/null\n+++ b/README.md\n@@ -0,0 +1 @@\n+# Title"
+    );
+    assert!(files[1].is_new() && !files[0].is_new());
+}
+
+#[test]
+fn test_parse_counts_hunk_lines() {
+    // a removed `-- ` line followed by an added `++ ` line is not a file header
+    let diff = "--- a/schema.sql\n+++ b/schema.sql\n@@ -1,2 +1,2 @@\n-- old comment\n\
+                ++ new comment\n select 1;\n\\ No newline at end of file\n\
+                --- a/lib.rs\n+++ b/lib.rs\n@@ -1 +1 @@\n-a\n+b\n";
+    let files = FileDiff::parse(diff);
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].path, "schema.sql");
+    assert_eq!(
+        files[0].hunks[0].lines,
+        [
+            "-- old comment",
+            "++ new comment",
+            " select 1;",
+            "\\ No newline at end of file"
+        ]
+    );
+    assert_eq!(files[1].path, "lib.rs");
+    assert_eq!(files[1].hunks[0].lines, ["-a", "+b"]);
+}
+
+#[test]
+fn test_display_deleted_file() {
+    let diff = "--- a/old.rs\n+++ /dev/null\n@@ -1,2 +0,0 @@\n-one\n-two";
+    let files = FileDiff::parse(diff);
+    assert_eq!(files[0].path, "old.rs");
+    assert!(files[0].is_deleted());
+    assert_eq!(files[0].to_string(), diff);
+}
+
+#[test]
+fn test_accepted_hunks() {
+    let files = FileDiff::parse(DIFF);
+    let accepted = accepted_hunks(files.clone(), &[vec![false, true], vec![false]]);
+    assert_eq!(accepted.len(), 1);
+    assert_eq!(accepted[0].path, "src/lib.rs");
+    assert_eq!(accepted[0].hunks, [files[0].hunks[1].clone()]);
+}
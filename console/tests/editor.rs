@@ -0,0 +1,88 @@
+use console::terminal::editor::Editor;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+    KeyEvent::new(code, modifiers)
+}
+
+#[test]
+fn test_cursor_movement() {
+    let mut editor = Editor::new();
+    editor.insert_str("hello world");
+    editor.move_home();
+    editor.insert_str("> ");
+    assert_eq!(editor.text(), "> hello world");
+    editor.move_end();
+    editor.move_word_left();
+    assert_eq!(editor.cursor(), 8);
+    editor.move_word_left();
+    assert_eq!(editor.cursor(), 2);
+    editor.move_word_right();
+    assert_eq!(editor.cursor(), 7);
+    editor.delete_forward();
+    editor.delete_backward();
+    assert_eq!(editor.text(), "> hellworld");
+}
+
+#[test]
+fn test_graphemes() {
+    let mut editor = Editor::new();
+    // 'e' followed by a combining acute accent is one grapheme
+    editor.insert_str("cafe\u{301}!");
+    editor.move_left();
+    editor.move_left();
+    assert_eq!(editor.cursor(), 3);
+    editor.delete_forward();
+    assert_eq!(editor.text(), "caf!");
+}
+
+#[test]
+fn test_multiple_lines() {
+    let mut editor = Editor::new();
+    editor.insert_str("first line\r\nsecond");
+    assert_eq!(editor.text(), "first line\nsecond");
+    assert!(editor.move_up());
+    assert_eq!(editor.cursor(), 6);
+    assert!(!editor.move_up());
+    assert!(editor.move_down());
+    assert!(!editor.move_down());
+    editor.move_home();
+    assert_eq!(editor.cursor(), 11);
+
+    assert!(editor.handle_key(key(KeyCode::Char('j'), KeyModifiers::CONTROL)));
+    assert_eq!(editor.text(), "first line\n\nsecond");
+    assert!(!editor.handle_key(key(KeyCode::Enter, KeyModifiers::NONE)));
+}
+
+#[test]
+fn test_handle_key() {
+    let mut editor = Editor::new();
+    for c in "fn main".chars() {
+        assert!(editor.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE)));
+    }
+    editor.handle_key(key(KeyCode::Left, KeyModifiers::CONTROL));
+    editor.handle_key(key(KeyCode::Backspace, KeyModifiers::ALT));
+    assert_eq!(editor.text(), "main");
+    assert!(!editor.handle_key(key(KeyCode::Char('x'), KeyModifiers::CONTROL)));
+    assert_eq!(editor.take(), "main");
+    assert!(editor.is_empty());
+}
+
+#[test]
+fn test_layout() {
+    let mut editor = Editor::new();
+    // wide characters take two cells and are never split across rows
+    editor.insert_str("ab日本\nc");
+    let layout = editor.layout(5);
+    assert_eq!(layout.rows, vec!["ab日", "本", "c"]);
+    assert_eq!(layout.cursor, (2, 1));
+
+    editor.move_up();
+    editor.move_end();
+    assert_eq!(editor.layout(5).cursor, (1, 2));
+
+    editor.set_text("abcd");
+    let layout = editor.layout(4);
+    assert_eq!(layout.rows, vec!["abcd", ""]);
+    assert_eq!(layout.cursor, (1, 0));
+}
@@ -0,0 +1,47 @@
+use console::history::History;
+
+#[test]
+fn test_persistence() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = History::default_path(dir.path());
+
+    let mut history = History::load(&path).unwrap();
+    assert!(history.entries().is_empty());
+    history.add("first").unwrap();
+    history.add("first").unwrap();
+    history.add("   ").unwrap();
+    history.add("multi\nline with a \\n").unwrap();
+
+    let history = History::load(&path).unwrap();
+    assert_eq!(history.entries(), ["first", "multi\nline with a \\n"]);
+    assert_eq!(history.path(), Some(path.as_path()));
+}
+
+#[test]
+fn test_navigation() {
+    let mut history = History::in_memory();
+    assert_eq!(history.previous_entry("draft"), None);
+    history.add("one").unwrap();
+    history.add("two").unwrap();
+
+    assert_eq!(history.next_entry(), None);
+    assert_eq!(history.previous_entry("draft"), Some("two"));
+    assert_eq!(history.previous_entry("two"), Some("one"));
+    assert_eq!(history.previous_entry("one"), None);
+    assert_eq!(history.next_entry(), Some("two"));
+    assert_eq!(history.next_entry(), Some("draft"));
+    assert_eq!(history.next_entry(), None);
+}
+
+#[test]
+fn test_search() {
+    let mut history = History::in_memory();
+    for entry in ["cargo build", "git status", "cargo test"] {
+        history.add(entry).unwrap();
+    }
+    let newest = history.entries().len();
+    assert_eq!(history.search("cargo", newest), Some(2));
+    assert_eq!(history.search("cargo", 2), Some(0));
+    assert_eq!(history.search("cargo", 0), None);
+    assert_eq!(history.search("docker", newest), None);
+}
@@ -0,0 +1,309 @@
+use console::common::{
+    Choice, Console as _, ConsoleError, ConsoleInput, FileDiff, Message, Observability, Permission,
+    PermissionRequest, ToolCallStatistics, ToolStatus, TurnStatistics,
+};
+use console::json::JsonLines;
+use serde_json::{Value, json};
+use std::io::Cursor;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt as _;
+
+/// Parses the events written by the console, one JSON object per line.
+fn events(output: &[u8]) -> Vec<Value> {
+    String::from_utf8(output.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_prompt_input() {
+    let input = b"{\"type\": \"prompt\", \"text\": \"hello world\"}\n\
+        not json\n\
+        {\"type\": \"prompt\", \"text\": \"/help\"}\n\
+        {\"type\": \"command\", \"name\": \"thinking\", \"args\": [\"on\"]}\n\
+        {\"type\": \"exit\"}\n";
+    let mut output = Vec::new();
+    let mut console = JsonLines::new_with_buffers(Cursor::new(input), &mut output);
+
+    assert_eq!(
+        console.prompt_input().await.unwrap(),
+        ConsoleInput::Prompt {
+            prompt: "hello world".to_string()
+        }
+    );
+    assert_eq!(
+        console.prompt_input().await.unwrap(),
+        ConsoleInput::Thinking(true)
+    );
+    assert_eq!(console.prompt_input().await.unwrap(), ConsoleInput::Exit);
+    assert!(matches!(
+        console.prompt_input().await,
+        Err(ConsoleError::Terminated)
+    ));
+
+    let events = events(&output);
+    let types: Vec<&str> = events
+        .iter()
+        .map(|event| event["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            "awaiting_input",
+            "awaiting_input",
+            "error",
+            "help",
+            "awaiting_input",
+            "awaiting_input",
+            "awaiting_input",
+        ]
+    );
+    assert!(events[3]["text"].as_str().unwrap().contains("/thinking"));
+}
+
+#[tokio::test]
+async fn test_response_events() {
+    let mut output = Vec::new();
+    let mut console = JsonLines::new_with_buffers(Cursor::new(b""), &mut output);
+
+    assert!(matches!(
+        console.add_response_delta("early".to_string()).await,
+        Err(ConsoleError::InvalidState)
+    ));
+    console.start_responding().await.unwrap();
+    console.add_response_delta("Hel".to_string()).await.unwrap();
+    console
+        .add_thinking_text("hidden".to_string())
+        .await
+        .unwrap();
+    console.observability(Some(Observability {
+        statistics: false,
+        thinking: true,
+    }));
+    console.add_thinking_delta("hmm".to_string()).await.unwrap();
+    console
+        .start_tool_call("shell".to_string(), "{\"command\":\"ls\"}".to_string())
+        .await
+        .unwrap();
+    console.add_tool_output(String::new()).await.unwrap();
+    console.add_tool_output("a.rs\n".to_string()).await.unwrap();
+    console
+        .finish_tool_call(
+            ToolStatus::Failed("exit status 1".to_string()),
+            Duration::from_millis(1500),
+        )
+        .await
+        .unwrap();
+    console
+        .add_message(Message::Warning("careful".to_string()))
+        .await
+        .unwrap();
+    console.stop_responding().await.unwrap();
+
+    assert_eq!(
+        events(&output),
+        [
+            json!({"type": "response_start"}),
+            json!({"type": "response_delta", "delta": "Hel"}),
+            json!({"type": "thinking_delta", "delta": "hmm"}),
+            json!({"type": "tool_start", "name": "shell", "arguments": "{\"command\":\"ls\"}"}),
+            json!({"type": "tool_output", "output": "a.rs\n"}),
+            json!({
+                "type": "tool_finish",
+                "name": "shell",
+                "success": false,
+                "error": "exit status 1",
+                "duration_ms": 1500
+            }),
+            json!({"type": "message", "message": {"kind": "warning", "content": "careful"}}),
+            json!({"type": "response_end"}),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_questions() {
+    let files =
+        FileDiff::parse("--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-a\n+A\n@@ -5 +5 @@\n-b\n+B\n");
+    let input = b"{\"type\": \"answer\", \"text\": \"too early\"}\n\
+        {\"type\": \"confirm\", \"answer\": false}\n\
+        {\"type\": \"select\", \"indices\": [0, 1]}\n\
+        {\"type\": \"select\", \"indices\": [1]}\n\
+        {\"type\": \"permission\", \"decision\": \"deny\", \"feedback\": \"use cargo\"}\n\
+        {\"type\": \"review\", \"accepted\": [[false, true]]}\n\
+        {\"type\": \"answer\", \"text\": \"blue\"}\n\
+        {\"type\": \"confirm\", \"answer\": true}\n";
+    let mut output = Vec::new();
+    let mut console = JsonLines::new_with_buffers(Cursor::new(input), &mut output);
+
+    console.start_responding().await.unwrap();
+    assert!(!console.if_accept("Apply?".to_string()).await.unwrap());
+    let choices = vec![Choice::new("gpt-4o"), Choice::new("gpt-4o-mini")];
+    assert_eq!(
+        console
+            .select("Model?".to_string(), choices, false)
+            .await
+            .unwrap(),
+        [1]
+    );
+    let request = PermissionRequest::new("shell".to_string(), "make test".to_string());
+    assert_eq!(
+        console.request_permission(request).await.unwrap(),
+        Permission::Deny("use cargo".to_string())
+    );
+    let accepted = console.review_diff(files.clone()).await.unwrap();
+    assert_eq!(accepted.len(), 1);
+    assert_eq!(accepted[0].hunks, files[0].hunks[1..]);
+    assert_eq!(
+        console.ask_user("Color?".to_string()).await.unwrap(),
+        "blue"
+    );
+    assert!(console.if_yes("Continue?".to_string()).await.unwrap());
+
+    let events = events(&output);
+    let types: Vec<&str> = events
+        .iter()
+        .map(|event| event["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            "response_start",
+            "confirm",
+            "error",
+            "select",
+            "error",
+            "permission",
+            "review",
+            "question",
+            "confirm",
+        ]
+    );
+    assert_eq!(events[1]["default"], true);
+    assert_eq!(events[8]["default"], false);
+    assert_eq!(
+        events[3]["choices"][0],
+        json!({"label": "gpt-4o", "description": null})
+    );
+    assert_eq!(events[5]["request"]["pattern"], "make test");
+    assert_eq!(events[6]["files"][0]["hunks"][1]["header"], "@@ -5 +5 @@");
+}
+
+#[tokio::test]
+async fn test_statistics_event() {
+    let mut output = Vec::new();
+    let mut console = JsonLines::new_with_buffers(Cursor::new(b""), &mut output);
+    let statistics = TurnStatistics {
+        input_tokens: 100,
+        output_tokens: 20,
+        model_calls: 2,
+        tool_calls: vec![ToolCallStatistics {
+            name: "shell".to_string(),
+            duration: Duration::from_millis(30),
+            success: true,
+        }],
+        duration: Duration::from_millis(1200),
+        ..TurnStatistics::default()
+    };
+
+    console.start_responding().await.unwrap();
+    console.add_statistics(statistics.clone()).await.unwrap();
+    console.observability(Some(Observability {
+        statistics: true,
+        thinking: false,
+    }));
+    console.add_statistics(statistics).await.unwrap();
+    console.stop_responding().await.unwrap();
+
+    assert_eq!(
+        events(&output)[1],
+        json!({
+            "type": "statistics",
+            "statistics": {
+                "input_tokens": 100,
+                "cached_tokens": 0,
+                "output_tokens": 20,
+                "model_calls": 2,
+                "tool_calls": [{"name": "shell", "duration_ms": 30, "success": true}],
+                "time_to_first_token_ms": null,
+                "duration_ms": 1200,
+                "cost": null
+            }
+        })
+    );
+    assert_eq!(events(&output).len(), 3);
+}
+
+#[tokio::test]
+async fn test_cancel_mid_turn() {
+    let (mut client, input) = tokio::io::duplex(1024);
+    let mut output = Vec::new();
+    let mut console = JsonLines::new_with_buffers(input, &mut output);
+
+    console.start_responding().await.unwrap();
+    let token = console.cancellation_token().unwrap();
+    client.write_all(b"{\"type\": \"cancel\"}\n").await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), token.cancelled())
+        .await
+        .unwrap();
+    assert!(matches!(
+        console.ask_user("More?".to_string()).await,
+        Err(ConsoleError::Cancelled)
+    ));
+    console.stop_responding().await.unwrap();
+
+    // the next turn has a new token, and a cancel outside of a turn is reported
+    client
+        .write_all(b"{\"type\": \"cancel\"}\n{\"type\": \"exit\"}\n")
+        .await
+        .unwrap();
+    assert_eq!(console.prompt_input().await.unwrap(), ConsoleInput::Exit);
+    console.start_responding().await.unwrap();
+    assert!(!console.cancellation_token().unwrap().is_cancelled());
+    drop(console);
+
+    let types: Vec<Value> = events(&output)
+        .iter()
+        .map(|event| event["type"].clone())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            "response_start",
+            "response_end",
+            "awaiting_input",
+            "error",
+            "awaiting_input",
+            "response_start"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_closed_input_cancels_turn() {
+    let (client, input) = tokio::io::duplex(1024);
+    let mut console = JsonLines::new_with_buffers(input, Vec::new());
+
+    console.start_responding().await.unwrap();
+    let token = console.cancellation_token().unwrap();
+    assert!(!token.is_cancelled());
+    drop(client);
+    tokio::time::timeout(Duration::from_secs(5), token.cancelled())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_write_only() {
+    let mut output = Vec::new();
+    let mut console = JsonLines::write_only(&mut output);
+
+    console.start_responding().await.unwrap();
+    assert!(matches!(
+        console.if_yes("Continue?".to_string()).await,
+        Err(ConsoleError::NoInput)
+    ));
+    assert!(!console.cancellation_token().unwrap().is_cancelled());
+}
@@ -0,0 +1,98 @@
+use console::terminal::markdown::{Progress, render, render_growing};
+use ratatui::style::{Color, Modifier};
+use ratatui::text::Line;
+
+fn text(lines: &[Line]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_blocks() {
+    let lines = render(
+        "# Title\n\nSome *emphasis* and **bold** text.\n\n- one\n- two\n  1. nested\n\n> quoted",
+    );
+    assert_eq!(
+        text(&lines),
+        [
+            "Title",
+            "",
+            "Some emphasis and bold text.",
+            "",
+            "• one",
+            "• two",
+            "  1. nested",
+            "",
+            "│ quoted",
+        ]
+    );
+    assert!(
+        lines[0].spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD)
+    );
+    let emphasis = lines[2]
+        .spans
+        .iter()
+        .find(|span| span.content == "emphasis")
+        .unwrap();
+    assert!(emphasis.style.add_modifier.contains(Modifier::ITALIC));
+}
+
+#[test]
+fn test_code_block() {
+    let lines = render("Run:\n\n```rust\nfn main() {}\n```\n");
+    assert_eq!(text(&lines), ["Run:", "", "```rust", "fn main() {}", "```"]);
+    // the code is highlighted
+    assert!(lines[3].spans.len() > 1);
+    assert!(matches!(lines[3].spans[0].style.fg, Some(Color::Rgb(..))));
+}
+
+#[test]
+fn test_streaming_code_block() {
+    // an unclosed fence is rendered as a code block up to the end of the text
+    let lines = render("```python\nprint('hi')");
+    assert_eq!(text(&lines), ["```python", "print('hi')", "```"]);
+}
+
+#[test]
+fn test_table() {
+    let lines = render("| name | size |\n|------|-----:|\n| a | 1 |\n| long name | 200 |\n");
+    assert_eq!(
+        text(&lines),
+        [
+            "name      │ size",
+            "──────────┼─────",
+            "a         │    1",
+            "long name │  200",
+        ]
+    );
+}
+
+#[test]
+fn test_render_growing() {
+    let answer = "# Plan\n\nFirst *check* the code:\n\n```rust\nfn main() {}\n```\n\n\
+                  1. one\n\n2. two\n\n> quoted\nstill quoted\n\n---\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+    let mut lines = Vec::new();
+    let mut progress = Progress::default();
+    let mut kept = 0;
+    for end in (1..=answer.len()).filter(|end| answer.is_char_boundary(*end)) {
+        kept = render_growing(&answer[..end], &mut lines, &mut progress);
+        assert_eq!(
+            text(&lines),
+            text(&render(&answer[..end])),
+            "{:?}",
+            &answer[..end]
+        );
+    }
+    // the blocks before the table are rendered for good
+    assert_eq!(text(&lines[kept..])[0], "a │ b");
+}
@@ -0,0 +1,46 @@
+use console::message::Message;
+
+#[test]
+fn test_plain_text() {
+    let code = Message::Code {
+        language: Some("rust".to_string()),
+        code: "fn main() {}\n".to_string(),
+    };
+    assert_eq!(code.to_string(), "```rust\nfn main() {}\n```");
+
+    let diff = Message::Diff {
+        path: Some("src/lib.rs".to_string()),
+        diff: "@@ -1 +1 @@\n-old\n+new\n".to_string(),
+    };
+    assert_eq!(diff.to_string(), "src/lib.rs:\n@@ -1 +1 @@\n-old\n+new");
+
+    let call = Message::ToolCall {
+        name: "read_file".to_string(),
+        arguments: r#"{"path":"a.txt"}"#.to_string(),
+    };
+    assert_eq!(call.to_string(), r#"Action: read_file({"path":"a.txt"})"#);
+
+    let result = Message::ToolResult {
+        name: "read_file".to_string(),
+        output: "not found".to_string(),
+        is_error: true,
+    };
+    assert_eq!(result.to_string(), "Observation: Error: not found");
+
+    assert_eq!(
+        Message::Warning("careful".to_string()).to_string(),
+        "Warning: careful"
+    );
+}
+
+#[test]
+fn test_table() {
+    let table = Message::Table {
+        headers: vec!["name".to_string(), "value".to_string()],
+        rows: vec![vec!["a|b".to_string(), "1".to_string()]],
+    };
+    assert_eq!(
+        table.to_string(),
+        "| name | value |\n|---|---|\n| a\\|b | 1 |"
+    );
+}
@@ -0,0 +1,60 @@
+use console::diff::FileDiff;
+use console::terminal::review::Review;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+fn files() -> Vec<FileDiff> {
+    FileDiff::parse(
+        "--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-a\n+A\n@@ -5 +5 @@\n-b\n+B\n\
+         --- a/c.rs\n+++ b/c.rs\n@@ -1 +1 @@\n-c\n+C\n",
+    )
+}
+
+#[test]
+fn test_decide_hunks() {
+    let mut review = Review::new(files());
+    assert_eq!(review.handle_key(key(KeyCode::Char('y'))), None);
+    assert_eq!(review.handle_key(key(KeyCode::Char('n'))), None);
+    // going back changes a decision
+    review.handle_key(key(KeyCode::Left));
+    review.handle_key(key(KeyCode::Char('y')));
+    assert_eq!(
+        review.decisions(),
+        [vec![Some(true), Some(true)], vec![None]]
+    );
+
+    // the undecided hunk is rejected
+    let accepted = review.handle_key(key(KeyCode::Enter)).unwrap();
+    assert_eq!(accepted.len(), 1);
+    assert_eq!(accepted[0].path, "a.rs");
+    assert_eq!(accepted[0].hunks.len(), 2);
+}
+
+#[test]
+fn test_decide_rest_of_file() {
+    // like in StdIo, `a` and `d` stop at the end of the file
+    let mut review = Review::new(files());
+    review.handle_key(key(KeyCode::Char('a')));
+    assert_eq!(
+        review.decisions(),
+        [vec![Some(true), Some(true)], vec![None]]
+    );
+    review.handle_key(key(KeyCode::Char('a')));
+    assert_eq!(review.handle_key(key(KeyCode::Enter)), Some(files()));
+
+    let mut review = Review::new(files());
+    review.handle_key(key(KeyCode::Char('y')));
+    review.handle_key(key(KeyCode::Char('d')));
+    review.handle_key(key(KeyCode::Char('y')));
+    assert_eq!(
+        review.decisions(),
+        [vec![Some(true), Some(false)], vec![Some(true)]]
+    );
+
+    let mut review = Review::new(files());
+    review.handle_key(key(KeyCode::Char('a')));
+    assert_eq!(review.handle_key(key(KeyCode::Esc)), Some(Vec::new()));
+}
@@ -0,0 +1,166 @@
+use console::common::{
+    Choice, Console as _, ConsoleError, ConsoleInput, FileDiff, Message, Permission,
+    PermissionRequest, ToolStatus,
+};
+use console::scripted::{Interaction, Record, ScriptedConsole};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_prompt_input() {
+    let mut console = ScriptedConsole::new([
+        Interaction::Input("hello".to_string()),
+        Interaction::Input("/help".to_string()),
+        Interaction::Input("/thinking on".to_string()),
+    ]);
+
+    assert_eq!(
+        console.prompt_input().await.unwrap(),
+        ConsoleInput::Prompt {
+            prompt: "hello".to_string()
+        }
+    );
+    assert_eq!(
+        console.prompt_input().await.unwrap(),
+        ConsoleInput::Thinking(true)
+    );
+    assert!(console.is_finished());
+    assert!(matches!(
+        console.prompt_input().await,
+        Err(ConsoleError::Terminated)
+    ));
+    assert_eq!(
+        console.transcript(),
+        [
+            Record::Input("hello".to_string()),
+            Record::Input("/help".to_string()),
+            Record::Help,
+            Record::Input("/thinking on".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_response_transcript() {
+    let mut console = ScriptedConsole::new([]);
+
+    assert!(matches!(
+        console.add_response_text("early".to_string()).await,
+        Err(ConsoleError::InvalidState)
+    ));
+    console.start_responding().await.unwrap();
+    console
+        .add_thinking_text("hidden or not".to_string())
+        .await
+        .unwrap();
+    console
+        .start_tool_call("shell".to_string(), "ls".to_string())
+        .await
+        .unwrap();
+    console.add_tool_output(String::new()).await.unwrap();
+    console.add_tool_output("a.rs\n".to_string()).await.unwrap();
+    console
+        .finish_tool_call(ToolStatus::Succeeded, Duration::from_millis(3))
+        .await
+        .unwrap();
+    console.add_response_delta("Hel".to_string()).await.unwrap();
+    console.add_response_delta("lo".to_string()).await.unwrap();
+    console
+        .add_message(Message::Warning("careful".to_string()))
+        .await
+        .unwrap();
+    console.add_response_text("Bye".to_string()).await.unwrap();
+    console.stop_responding().await.unwrap();
+
+    assert_eq!(
+        console.transcript(),
+        [
+            Record::StartResponding,
+            Record::ThinkingText("hidden or not".to_string()),
+            Record::ToolStart {
+                name: "shell".to_string(),
+                arguments: "ls".to_string()
+            },
+            Record::ToolOutput("a.rs\n".to_string()),
+            Record::ToolFinish(ToolStatus::Succeeded),
+            Record::ResponseDelta("Hel".to_string()),
+            Record::ResponseDelta("lo".to_string()),
+            Record::Message(Message::Warning("careful".to_string())),
+            Record::ResponseText("Bye".to_string()),
+            Record::StopResponding,
+        ]
+    );
+    assert_eq!(console.response(), "HelloBye\n");
+}
+
+#[tokio::test]
+async fn test_questions() {
+    let files =
+        FileDiff::parse("--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-a\n+A\n@@ -5 +5 @@\n-b\n+B\n");
+    let mut console = ScriptedConsole::new([
+        Interaction::Confirm(false),
+        Interaction::Select(vec![1]),
+        Interaction::Permission(Permission::Deny("use cargo".to_string())),
+        Interaction::Review(vec![vec![false, true]]),
+        Interaction::Answer("blue".to_string()),
+        Interaction::Cancel,
+    ]);
+
+    console.start_responding().await.unwrap();
+    let token = console.cancellation_token().unwrap();
+    assert!(!console.if_accept("Apply?".to_string()).await.unwrap());
+    let choices = vec![Choice::new("gpt-4o"), Choice::new("gpt-4o-mini")];
+    assert_eq!(
+        console
+            .select("Model?".to_string(), choices.clone(), false)
+            .await
+            .unwrap(),
+        [1]
+    );
+    let request = PermissionRequest::new("shell".to_string(), "make test".to_string());
+    assert_eq!(
+        console.request_permission(request.clone()).await.unwrap(),
+        Permission::Deny("use cargo".to_string())
+    );
+    let accepted = console.review_diff(files.clone()).await.unwrap();
+    assert_eq!(accepted[0].hunks, files[0].hunks[1..]);
+    assert_eq!(
+        console.ask_user("Color?".to_string()).await.unwrap(),
+        "blue"
+    );
+    assert!(matches!(
+        console.if_yes("Continue?".to_string()).await,
+        Err(ConsoleError::Cancelled)
+    ));
+    assert!(token.is_cancelled());
+    assert!(matches!(
+        console.ask_user("More?".to_string()).await,
+        Err(ConsoleError::Cancelled)
+    ));
+
+    assert_eq!(
+        console.transcript(),
+        [
+            Record::StartResponding,
+            Record::Accept("Apply?".to_string()),
+            Record::Select {
+                text: "Model?".to_string(),
+                choices,
+                multiple: false
+            },
+            Record::Permission(request),
+            Record::Review(files),
+            Record::Question("Color?".to_string()),
+            Record::Confirm("Continue?".to_string()),
+            Record::Cancelled,
+        ]
+    );
+}
+
+#[tokio::test]
+#[should_panic(expected = "ask_user doesn't match the next interaction Confirm(true)")]
+async fn test_unexpected_interaction() {
+    let mut console = ScriptedConsole::new([Interaction::Confirm(true)]);
+
+    console.start_responding().await.unwrap();
+    let _ = console.ask_user("Name?".to_string()).await;
+}
@@ -0,0 +1,65 @@
+use console::statistics::{SessionStatistics, ToolCallStatistics, TurnStatistics};
+use std::time::Duration;
+
+fn turn() -> TurnStatistics {
+    TurnStatistics {
+        input_tokens: 1200,
+        cached_tokens: 300,
+        output_tokens: 250,
+        model_calls: 2,
+        tool_calls: vec![
+            ToolCallStatistics {
+                name: "shell".to_string(),
+                duration: Duration::from_millis(1500),
+                success: true,
+            },
+            ToolCallStatistics {
+                name: "edit".to_string(),
+                duration: Duration::from_millis(200),
+                success: false,
+            },
+        ],
+        time_to_first_token: Some(Duration::from_millis(800)),
+        duration: Duration::from_millis(4200),
+        cost: Some(0.0123),
+    }
+}
+
+#[test]
+fn test_turn_report() {
+    assert_eq!(
+        turn().to_string(),
+        "Tokens: 1200 in (300 cached), 250 out\n\
+         Model calls: 2, first token after 0.8s\n\
+         Tool calls: shell 1.5s, edit 0.2s (failed)\n\
+         Response time: 4.2s\n\
+         Cost: $0.0123"
+    );
+    assert_eq!(
+        TurnStatistics::default().to_string(),
+        "Tokens: 0 in, 0 out\nModel calls: 0\nResponse time: 0.0s"
+    );
+}
+
+#[test]
+fn test_session_totals() {
+    let mut session = SessionStatistics::default();
+    session.add_turn(&TurnStatistics {
+        cost: None,
+        ..turn()
+    });
+    assert_eq!(session.cost, None);
+    session.add_turn(&turn());
+    session.add_turn(&turn());
+
+    assert_eq!(session.turns, 3);
+    assert_eq!(session.tool_calls, 6);
+    assert!((session.cost.unwrap() - 0.0246).abs() < 1e-9);
+    assert_eq!(
+        session.to_string(),
+        "Session: 3 turns, 6 model calls, 6 tool calls (5.1s)\n\
+         Tokens: 3600 in (900 cached), 750 out\n\
+         Response time: 12.6s\n\
+         Cost: $0.0246"
+    );
+}
@@ -0,0 +1,33 @@
+use console::status::{ConsoleMode, Status};
+
+#[test]
+fn test_line() {
+    assert_eq!(Status::default().line(ConsoleMode::Prompting), "prompting");
+    let status = Status {
+        model: Some("gpt-4o".to_string()),
+        context_usage: Some(7),
+        cost: Some(0.01234),
+        working_dir: Some("~/project".to_string()),
+        git_branch: Some("main".to_string()),
+        git_dirty: false,
+    };
+    assert_eq!(
+        status.line(ConsoleMode::AwaitingApproval),
+        "gpt-4o | context 7% | $0.0123 | ~/project | main | awaiting approval"
+    );
+}
+
+#[test]
+fn test_refresh_workspace_outside_of_git() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut status = Status {
+        git_branch: Some("stale".to_string()),
+        git_dirty: true,
+        ..Status::default()
+    };
+
+    status.refresh_workspace(dir.path());
+    assert_eq!(status.working_dir, Some(dir.path().display().to_string()));
+    assert_eq!(status.git_branch, None);
+    assert!(!status.git_dirty);
+}
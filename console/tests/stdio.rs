@@ -1,6 +1,14 @@
-use console::common::{Console, ConsoleError, ConsoleInput, Observability};
+use console::command::{ArgSpec, CommandSpec};
+use console::common::{
+    Choice, Console as _, ConsoleError, ConsoleInput, FileDiff, Observability, Permission,
+    PermissionRequest, ToolStatus, TurnStatistics,
+};
+use console::history::History;
+use console::message::Message;
 use console::stdio::StdIo;
 use std::io::Cursor;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt as _;
 
 #[tokio::test]
 async fn test_create_console() {
@@ -128,6 +136,38 @@ async fn test_statistics_no_printing() {
     assert!(!output_str.contains("Response time:"));
 }
 
+#[tokio::test]
+async fn test_turn_statistics_printing() {
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&b""[..], &mut output);
+    console.observability(Some(Observability {
+        statistics: true,
+        thinking: false,
+    }));
+
+    console.start_responding().await.unwrap();
+    console
+        .add_response_delta("Done".to_string())
+        .await
+        .unwrap();
+    console
+        .add_statistics(TurnStatistics {
+            input_tokens: 100,
+            output_tokens: 20,
+            model_calls: 1,
+            duration: Duration::from_millis(1200),
+            ..TurnStatistics::default()
+        })
+        .await
+        .unwrap();
+    console.stop_responding().await.unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "Done\nTokens: 100 in, 20 out\nModel calls: 1\nResponse time: 1.2s\n\n"
+    );
+}
+
 #[tokio::test]
 async fn test_thinking_printing() {
     let mut output = Vec::new();
@@ -172,7 +212,7 @@ async fn test_command_parsing() {
 
     for (input_str, expected) in scenarios {
         let input = format!("{}\n", input_str);
-        let mut console = StdIo::new_with_buffers(&input.as_bytes()[..], Vec::new());
+        let mut console = StdIo::new_with_buffers(input.as_bytes(), Vec::new());
         let result = console.prompt_input().await;
 
         match (result, expected) {
@@ -253,3 +293,366 @@ async fn test_if_accept() {
     let output_str = String::from_utf8(output).unwrap();
     assert_eq!(output_str.matches("[Y/n]").count(), 8);
 }
+
+#[tokio::test]
+async fn test_response_deltas() {
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(Cursor::new(b""), &mut output);
+    console.observability(Some(Observability { statistics: false, thinking: true }));
+
+    console.start_responding().await.unwrap();
+    console.add_thinking_delta("hmm, ".to_string()).await.unwrap();
+    console.add_thinking_delta("let me see".to_string()).await.unwrap();
+    console.add_response_delta("Hel".to_string()).await.unwrap();
+    console.add_response_delta("lo".to_string()).await.unwrap();
+    console.add_response_text("block".to_string()).await.unwrap();
+    console.add_response_delta("tail".to_string()).await.unwrap();
+    console.stop_responding().await.unwrap();
+
+    let output_str = String::from_utf8(output).unwrap();
+    assert_eq!(output_str, "hmm, let me see\nHello\nblock\ntail\n");
+}
+
+#[tokio::test]
+async fn test_thinking_deltas_no_printing() {
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(Cursor::new(b""), &mut output);
+
+    console.start_responding().await.unwrap();
+    console.add_thinking_delta("hidden".to_string()).await.unwrap();
+    console.add_response_delta("shown".to_string()).await.unwrap();
+    console.stop_responding().await.unwrap();
+
+    let output_str = String::from_utf8(output).unwrap();
+    assert_eq!(output_str, "shown\n");
+}
+
+#[tokio::test]
+async fn test_cancellation_token() {
+    let input = b"y\n";
+    let mut console = StdIo::new_with_buffers(&input[..], Vec::new());
+
+    // There is no turn to cancel while prompting
+    assert!(console.cancellation_token().is_none());
+
+    console.start_responding().await.unwrap();
+    let token = console.cancellation_token().unwrap();
+    assert!(!token.is_cancelled());
+
+    // Questions fail once the turn is cancelled
+    token.cancel();
+    assert!(matches!(
+        console.if_yes("Question?".to_string()).await,
+        Err(ConsoleError::Cancelled)
+    ));
+
+    // Every turn gets a fresh token
+    console.stop_responding().await.unwrap();
+    assert!(console.cancellation_token().is_none());
+    console.start_responding().await.unwrap();
+    assert!(!console.cancellation_token().unwrap().is_cancelled());
+}
+
+#[tokio::test]
+async fn test_help_command() {
+    let input = b"/help\n/deploy prod\n";
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&input[..], &mut output);
+    console.commands().register(
+        CommandSpec::new("deploy", "Deploy the project").arg(ArgSpec::required("target")),
+    );
+
+    // The help is printed by the console, the next line is returned
+    assert_eq!(
+        console.prompt_input().await.unwrap(),
+        ConsoleInput::Command { name: "deploy".to_string(), args: vec!["prod".to_string()] }
+    );
+    let output_str = String::from_utf8(output).unwrap();
+    assert!(output_str.contains("/deploy <target>"));
+    assert!(output_str.contains("/exit"));
+}
+
+#[tokio::test]
+async fn test_status_command() {
+    let input = b"/status\n/exit\n";
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&input[..], &mut output);
+    let status = console.status();
+    status.model = Some("gpt-4o".to_string());
+    status.context_usage = Some(42);
+    status.git_branch = Some("main".to_string());
+    status.git_dirty = true;
+
+    assert_eq!(console.prompt_input().await.unwrap(), ConsoleInput::Exit);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "gpt-4o | context 42% | main* | prompting\n"
+    );
+}
+
+#[tokio::test]
+async fn test_history() {
+    let input = b"first prompt\n/exit\n";
+    let mut output = Vec::new();
+    let mut console =
+        StdIo::new_with_buffers(&input[..], &mut output).with_history(History::in_memory());
+
+    console.prompt_input().await.unwrap();
+    console.prompt_input().await.unwrap();
+    assert_eq!(console.history().entries(), ["first prompt", "/exit"]);
+}
+
+#[tokio::test]
+async fn test_add_message() {
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(Cursor::new(b""), &mut output);
+
+    assert!(matches!(
+        console.add_message(Message::Error("early".to_string())).await,
+        Err(ConsoleError::InvalidState)
+    ));
+    console.start_responding().await.unwrap();
+    console.add_response_delta("partial".to_string()).await.unwrap();
+    console
+        .add_message(Message::Error("failed".to_string()))
+        .await
+        .unwrap();
+    console.stop_responding().await.unwrap();
+
+    let output_str = String::from_utf8(output).unwrap();
+    assert_eq!(output_str, "partial\nError: failed\n");
+}
+
+#[tokio::test]
+async fn test_tool_call() {
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(Cursor::new(b""), &mut output);
+
+    console.start_responding().await.unwrap();
+    assert!(matches!(
+        console.add_tool_output("early".to_string()).await,
+        Err(ConsoleError::InvalidState)
+    ));
+    console
+        .start_tool_call("shell".to_string(), "x".repeat(100))
+        .await
+        .unwrap();
+    console
+        .add_tool_output("compiling\n".to_string())
+        .await
+        .unwrap();
+    console
+        .finish_tool_call(ToolStatus::Succeeded, Duration::from_millis(2345))
+        .await
+        .unwrap();
+    console.stop_responding().await.unwrap();
+
+    let output_str = String::from_utf8(output).unwrap();
+    assert_eq!(
+        output_str,
+        format!(
+            "Action: shell({}…)\nFinished shell in 2.3s\n",
+            "x".repeat(79)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_review_diff() {
+    let files = FileDiff::parse(
+        "--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-a\n+A\n@@ -5 +5 @@\n-b\n+B\n\
+         --- a/c.rs\n+++ b/c.rs\n@@ -1 +1 @@\n-c\n+C\n@@ -9 +9 @@\n-d\n+D\n",
+    );
+    let input = b"?\nn\ny\na\n";
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&input[..], &mut output);
+
+    console.start_responding().await.unwrap();
+    let accepted = console.review_diff(files.clone()).await.unwrap();
+    assert_eq!(
+        accepted,
+        [
+            FileDiff {
+                path: "a.rs".to_string(),
+                hunks: vec![files[0].hunks[1].clone()],
+            },
+            files[1].clone(),
+        ]
+    );
+
+    let output_str = String::from_utf8(output).unwrap();
+    assert!(output_str.starts_with("--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-a\n+A\n"));
+    assert!(output_str.contains("(1/2) Accept this hunk [y,n,a,d,q,?]? y - accept this hunk"));
+    assert_eq!(output_str.matches("(1/2) Accept").count(), 3);
+}
+
+#[tokio::test]
+async fn test_request_permission() {
+    let request = PermissionRequest::new("shell".to_string(), "git status".to_string());
+    let input = b"s\nuse git diff instead\n";
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&input[..], &mut output);
+
+    console.start_responding().await.unwrap();
+    assert_eq!(
+        console.request_permission(request.clone()).await.unwrap(),
+        Permission::AllowSession
+    );
+    assert_eq!(
+        console.request_permission(request).await.unwrap(),
+        Permission::Deny("use git diff instead".to_string())
+    );
+    assert!(matches!(
+        console
+            .request_permission(PermissionRequest::new("shell".to_string(), "ls".to_string()))
+            .await,
+        Err(ConsoleError::Terminated)
+    ));
+
+    let output_str = String::from_utf8(output).unwrap();
+    assert!(output_str.starts_with(
+        "Allow shell: git status?\n[y]es once, [s]ession or [a]lways for `git status *`"
+    ));
+}
+
+#[tokio::test]
+async fn test_select() {
+    let choices = vec![
+        Choice::new("gpt-4o").with_description("the default"),
+        Choice::new("gpt-4o-mini"),
+        Choice::new("o1"),
+    ];
+    let input = b"4\n2\n3, 1 3\nx\n\n";
+    let mut output = Vec::new();
+    let mut console = StdIo::new_with_buffers(&input[..], &mut output);
+
+    console.start_responding().await.unwrap();
+    assert_eq!(
+        console
+            .select("Model?".to_string(), choices.clone(), false)
+            .await
+            .unwrap(),
+        [1]
+    );
+    assert_eq!(
+        console
+            .select("Models?".to_string(), choices.clone(), true)
+            .await
+            .unwrap(),
+        [0, 2]
+    );
+    // an empty answer chooses nothing
+    assert_eq!(
+        console
+            .select("Models?".to_string(), choices, true)
+            .await
+            .unwrap(),
+        Vec::<usize>::new()
+    );
+
+    let output_str = String::from_utf8(output).unwrap();
+    assert!(output_str.starts_with(
+        "Model?\n  1. gpt-4o\n     the default\n  2. gpt-4o-mini\n  3. o1\nEnter a number [1-3]: \
+         Please enter one number between 1 and 3.\n"
+    ));
+    assert!(output_str.contains("Please enter numbers between 1 and 3."));
+}
+
+#[tokio::test]
+async fn test_prompt_input_does_not_block_the_runtime() {
+    // the single-threaded test runtime can only run the writer while the console waits
+    let (reader, mut writer) = tokio::io::duplex(64);
+    tokio::spawn(async move { writer.write_all(b"hello from a task\n").await });
+    let mut console = StdIo::new_with_buffers(reader, Vec::new());
+    assert_eq!(
+        console.prompt_input().await.unwrap(),
+        ConsoleInput::Prompt {
+            prompt: "hello from a task".to_string()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_prompt_input_skips_blank_lines() {
+    let input = b"\n   \nhello\n";
+    let mut console = StdIo::new_with_buffers(&input[..], Vec::new());
+    assert_eq!(
+        console.prompt_input().await.unwrap(),
+        ConsoleInput::Prompt {
+            prompt: "hello".to_string()
+        }
+    );
+    assert!(matches!(
+        console.prompt_input().await,
+        Err(ConsoleError::Terminated)
+    ));
+}
+
+#[tokio::test]
+async fn test_prompt_input_multi_line() {
+    let input = "why does this fail?\\\n  at main.rs:3\\\n\ndone\n\
+        \"\"\"\nfn main() {\n\n    panic!()\n}\n\"\"\"\n\
+        \"\"\"one line\"\"\"\n\
+        cut \\\n";
+    let mut console = StdIo::new_with_buffers(input.as_bytes(), Vec::new());
+    let mut prompts = Vec::new();
+    while let Ok(ConsoleInput::Prompt { prompt }) = console.prompt_input().await {
+        prompts.push(prompt);
+    }
+    assert_eq!(
+        prompts,
+        [
+            "why does this fail?\n  at main.rs:3",
+            "done",
+            "fn main() {\n\n    panic!()\n}",
+            "one line",
+            "cut",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_prompt_block_keeps_indentation() {
+    let input = "\"\"\"\n    let x = 1;\n    x + 1\n\"\"\"\n  \"\"\"  indented\n  done\"\"\"\n";
+    let mut console = StdIo::new_with_buffers(input.as_bytes(), Vec::new());
+    let mut prompts = Vec::new();
+    while let Ok(ConsoleInput::Prompt { prompt }) = console.prompt_input().await {
+        prompts.push(prompt);
+    }
+    assert_eq!(prompts, ["    let x = 1;\n    x + 1", "  indented\n  done"]);
+}
+
+#[tokio::test]
+async fn test_questions_at_end_of_input() {
+    let mut console = StdIo::new_with_buffers(&b""[..], Vec::new());
+    console.start_responding().await.unwrap();
+    assert!(matches!(
+        console.if_accept("Question?".to_string()).await,
+        Err(ConsoleError::Terminated)
+    ));
+    assert!(matches!(
+        console.ask_user("Question?".to_string()).await,
+        Err(ConsoleError::Terminated)
+    ));
+}
+
+#[tokio::test]
+async fn test_write_only() {
+    let mut output = Vec::new();
+    let mut console = StdIo::write_only(&mut output);
+    console.start_responding().await.unwrap();
+    assert!(matches!(
+        console.if_yes("Question?".to_string()).await,
+        Err(ConsoleError::NoInput)
+    ));
+    assert!(matches!(
+        console.prompt_input().await,
+        Err(ConsoleError::InvalidState)
+    ));
+    console.stop_responding().await.unwrap();
+    assert!(matches!(
+        console.prompt_input().await,
+        Err(ConsoleError::NoInput)
+    ));
+    assert_eq!(String::from_utf8(output).unwrap(), "Question? [y/N]\n");
+}
@@ -1,4 +1,9 @@
+use console::common::{Console as _, ConsoleError, ConsoleInput};
 use console::terminal::Terminal;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::backend::TestBackend;
+use std::sync::mpsc::{Sender, channel};
+use std::time::Duration;
 
 #[test]
 fn test_create_and_drop_terminal() {
@@ -16,3 +21,244 @@ fn test_create_and_drop_terminal() {
         println!("Could not create terminal, skipping test.");
     }
 }
+
+/// Creates a terminal on a `TestBackend`, driven by the returned sender of events.
+fn headless() -> (Terminal<TestBackend>, Sender<Event>) {
+    let (keys, events) = channel();
+    let terminal = Terminal::with_backend(TestBackend::new(60, 16), events).unwrap();
+    (terminal, keys)
+}
+
+fn press(keys: &Sender<Event>, code: KeyCode) {
+    keys.send(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+        .unwrap();
+}
+
+fn type_text(keys: &Sender<Event>, text: &str) {
+    for c in text.chars() {
+        press(keys, KeyCode::Char(c));
+    }
+}
+
+/// Returns the rows of the screen, without their trailing spaces.
+fn screen(terminal: &Terminal<TestBackend>) -> Vec<String> {
+    let buffer = terminal.backend().buffer();
+    buffer
+        .content()
+        .chunks(buffer.area.width as usize)
+        .map(|row| {
+            let text: String = row.iter().map(|cell| cell.symbol()).collect();
+            text.trim_end().to_string()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_prompt_and_response() {
+    let (mut terminal, keys) = headless();
+    type_text(&keys, "hello");
+    press(&keys, KeyCode::Enter);
+    assert_eq!(
+        terminal.prompt_input().await.unwrap(),
+        ConsoleInput::Prompt {
+            prompt: "hello".to_string()
+        }
+    );
+
+    terminal.start_responding().await.unwrap();
+    terminal
+        .add_response_text("Hi there!".to_string())
+        .await
+        .unwrap();
+    terminal.stop_responding().await.unwrap();
+
+    let screen = screen(&terminal);
+    assert_eq!(
+        screen[0],
+        "┌Messages──────────────────────────────────────────────────┐"
+    );
+    assert_eq!(
+        screen[1],
+        "│> hello                                                   │"
+    );
+    assert!(screen.iter().any(|row| row.contains("Hi there!")));
+    assert_eq!(
+        screen[screen.len() - 3],
+        "┌Input─────────────────────────────────────────────────────┐"
+    );
+}
+
+#[tokio::test]
+async fn test_streamed_response_is_drawn_as_a_whole() {
+    let answer = "# Plan\n\n1. read\n\n2. fix\n\n```rust\nfn main() {}\n```\n\nDone.";
+    let (mut streamed, _keys) = headless();
+    streamed.start_responding().await.unwrap();
+    for delta in answer.split_inclusive([' ', '\n', '.']) {
+        streamed
+            .add_response_delta(delta.to_string())
+            .await
+            .unwrap();
+    }
+    streamed.stop_responding().await.unwrap();
+
+    let (mut whole, _keys) = headless();
+    whole.start_responding().await.unwrap();
+    whole.add_response_text(answer.to_string()).await.unwrap();
+    whole.stop_responding().await.unwrap();
+
+    assert_eq!(screen(&streamed), screen(&whole));
+    assert!(screen(&whole).iter().any(|row| row.contains("2. fix")));
+}
+
+#[tokio::test]
+async fn test_up_moves_one_line() {
+    let (mut terminal, keys) = headless();
+    for line in ["aaa", "bbb"] {
+        type_text(&keys, line);
+        keys.send(Event::Key(KeyEvent::new(
+            KeyCode::Enter,
+            KeyModifiers::SHIFT,
+        )))
+        .unwrap();
+    }
+    type_text(&keys, "ccc");
+    press(&keys, KeyCode::Up);
+    type_text(&keys, "X");
+    press(&keys, KeyCode::Down);
+    type_text(&keys, "Y");
+    press(&keys, KeyCode::Enter);
+    assert_eq!(
+        terminal.prompt_input().await.unwrap(),
+        ConsoleInput::Prompt {
+            prompt: "aaa\nbbbX\ncccY".to_string()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_blank_input_is_ignored() {
+    let (mut terminal, keys) = headless();
+    press(&keys, KeyCode::Enter);
+    type_text(&keys, "  ");
+    press(&keys, KeyCode::Enter);
+    type_text(&keys, "hi");
+    press(&keys, KeyCode::Enter);
+    assert_eq!(
+        terminal.prompt_input().await.unwrap(),
+        ConsoleInput::Prompt {
+            prompt: "  hi".to_string()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_confirm_dialog() {
+    let (mut terminal, keys) = headless();
+    terminal.start_responding().await.unwrap();
+    press(&keys, KeyCode::Char('n'));
+    assert!(
+        !terminal
+            .if_accept("Apply the patch?".to_string())
+            .await
+            .unwrap()
+    );
+    terminal
+        .add_response_text("Skipped.".to_string())
+        .await
+        .unwrap();
+    terminal.stop_responding().await.unwrap();
+    assert!(
+        screen(&terminal)
+            .iter()
+            .any(|row| row.contains("Apply the patch? [Y/n] no"))
+    );
+}
+
+#[tokio::test]
+async fn test_esc_cancels_the_turn() {
+    let (mut terminal, keys) = headless();
+    terminal.start_responding().await.unwrap();
+    let token = terminal.cancellation_token().unwrap();
+    press(&keys, KeyCode::Esc);
+    tokio::time::timeout(Duration::from_secs(5), token.cancelled())
+        .await
+        .expect("Esc should cancel the turn");
+    terminal.stop_responding().await.unwrap();
+
+    // closing the event source ends the session
+    drop(keys);
+    assert!(matches!(
+        terminal.prompt_input().await,
+        Err(ConsoleError::Terminated)
+    ));
+}
+
+#[tokio::test]
+async fn test_status_bar() {
+    let (mut terminal, keys) = headless();
+    let status = terminal.status();
+    status.model = Some("gpt-4o".to_string());
+    status.context_usage = Some(42);
+
+    terminal.start_responding().await.unwrap();
+    terminal
+        .add_response_text("Working on it".to_string())
+        .await
+        .unwrap();
+    let last_row = screen(&terminal).pop().unwrap();
+    assert!(last_row.starts_with("└ gpt-4o | context 42% | responding ─"));
+    terminal.stop_responding().await.unwrap();
+
+    drop(keys);
+    assert!(matches!(
+        terminal.prompt_input().await,
+        Err(ConsoleError::Terminated)
+    ));
+    let last_row = screen(&terminal).pop().unwrap();
+    assert!(last_row.starts_with("└ gpt-4o | context 42% | prompting ─"));
+}
+
+#[tokio::test]
+async fn test_inline_viewport_writes_scrollback() {
+    let (keys, events) = channel();
+    let mut terminal = Terminal::inline_with_backend(TestBackend::new(60, 16), events, 8).unwrap();
+    type_text(&keys, "hello");
+    press(&keys, KeyCode::Enter);
+    terminal.prompt_input().await.unwrap();
+
+    terminal.start_responding().await.unwrap();
+    terminal
+        .add_response_delta("Hi ".to_string())
+        .await
+        .unwrap();
+    terminal
+        .add_response_delta("there!".to_string())
+        .await
+        .unwrap();
+    // the finished prompt is above the viewport, which starts at the cursor, the streamed
+    // response is still in the viewport
+    let streaming = screen(&terminal);
+    assert_eq!(streaming[0], "> hello");
+    assert_eq!(
+        streaming[1],
+        "┌Messages──────────────────────────────────────────────────┐"
+    );
+    assert_eq!(
+        streaming[2],
+        "│Hi there!                                                 │"
+    );
+    terminal.stop_responding().await.unwrap();
+
+    drop(keys);
+    assert!(matches!(
+        terminal.prompt_input().await,
+        Err(ConsoleError::Terminated)
+    ));
+    let screen = screen(&terminal);
+    assert_eq!(screen[..2], ["> hello", "Hi there!"]);
+    assert_eq!(
+        screen[2],
+        "┌Messages──────────────────────────────────────────────────┐"
+    );
+    assert_eq!(screen[10..], ["", "", "", "", "", ""]);
+}
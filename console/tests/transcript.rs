@@ -0,0 +1,100 @@
+use console::terminal::transcript::{Transcript, wrap};
+use ratatui::text::Line;
+
+fn messages(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("message {}", i)).collect()
+}
+
+/// Lays out plain text messages and returns the visible rows as text.
+fn view(
+    transcript: &mut Transcript,
+    messages: &[String],
+    width: usize,
+    height: usize,
+) -> Vec<String> {
+    let lines: Vec<Vec<Line<'static>>> = messages
+        .iter()
+        .map(|message| {
+            message
+                .lines()
+                .map(|line| Line::raw(line.to_string()))
+                .collect()
+        })
+        .collect();
+    transcript
+        .view(lines.iter().map(Vec::as_slice), width, height)
+        .iter()
+        .map(|row| row.spans.iter().map(|span| span.content.as_ref()).collect())
+        .collect()
+}
+
+#[test]
+fn test_wrap() {
+    assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+    assert_eq!(wrap("abcdefghij klm", 4), ["abcd", "efgh", "ij", "klm"]);
+    assert_eq!(wrap("one\n\ntwo", 10), ["one", "", "two"]);
+    assert_eq!(wrap("", 10), [""]);
+    // wide characters take two cells
+    assert_eq!(wrap("日本語 中文", 6), ["日本語", "中文"]);
+    assert_eq!(wrap("テキスト", 6), ["テキス", "ト"]);
+}
+
+#[test]
+fn test_follow_tail() {
+    let mut transcript = Transcript::new();
+    assert_eq!(
+        view(&mut transcript, &messages(2), 20, 3),
+        ["message 0", "message 1"]
+    );
+    assert_eq!(
+        view(&mut transcript, &messages(5), 20, 3),
+        ["message 2", "message 3", "message 4"]
+    );
+    assert!(transcript.is_following());
+}
+
+#[test]
+fn test_scrolling() {
+    let mut transcript = Transcript::new();
+    view(&mut transcript, &messages(10), 20, 3);
+
+    transcript.page_up();
+    assert!(!transcript.is_following());
+    assert_eq!(
+        view(&mut transcript, &messages(10), 20, 3),
+        ["message 5", "message 6", "message 7"]
+    );
+
+    // new output doesn't move the view but is signalled
+    transcript.output_added();
+    assert!(transcript.has_unseen_output());
+    assert_eq!(
+        view(&mut transcript, &messages(11), 20, 3),
+        ["message 5", "message 6", "message 7"]
+    );
+
+    transcript.scroll_up(100);
+    assert_eq!(view(&mut transcript, &messages(11), 20, 3)[0], "message 0");
+
+    transcript.scroll_down(100);
+    assert!(transcript.is_following());
+    assert!(!transcript.has_unseen_output());
+    assert_eq!(view(&mut transcript, &messages(11), 20, 3)[2], "message 10");
+}
+
+#[test]
+fn test_reflow() {
+    let mut transcript = Transcript::new();
+    let messages = vec![
+        "a b c d".to_string(),
+        "e f g h".to_string(),
+        "end".to_string(),
+    ];
+    assert_eq!(view(&mut transcript, &messages, 3, 2), ["g h", "end"]);
+    transcript.scroll_up(1);
+    assert_eq!(view(&mut transcript, &messages, 3, 2), ["e f", "g h"]);
+
+    // the anchored message stays at the top after a resize
+    assert_eq!(view(&mut transcript, &messages, 7, 2), ["e f g h", "end"]);
+    assert!(transcript.is_following());
+}
@@ -1,3 +1,351 @@
-fn main() {
-    println!("Hello, world!");
+use agent::builtin;
+use agent::provider;
+use agent::react::{Agent, AgentError};
+use console::command::CommandSpec;
+use console::common::{
+    Choice, Console, ConsoleError, ConsoleInput, ConsoleMode, Message, Observability,
+};
+use console::custom;
+use console::history::History;
+use console::json::JsonLines;
+use console::permission::Permissions;
+use console::stdio::StdIo;
+use console::terminal::Terminal;
+use oneshot::OutputFormat;
+use std::io::{IsTerminal as _, Read as _};
+
+mod oneshot;
+
+const DEFAULT_PROVIDER: &str = "openai";
+const DEFAULT_MODEL: &str = "gpt-4o";
+
+/// The models offered by `/model`, as provider and model names.
+const MODELS: &[(&str, &str)] = &[
+    ("openai", "gpt-4o"),
+    ("openai", "gpt-4o-mini"),
+    ("anthropic", "claude-3-5-sonnet-latest"),
+    ("gemini", "gemini-1.5-pro"),
+];
+
+/// The height of the Terminal UI with `--inline`, in rows.
+const INLINE_HEIGHT: u16 = 12;
+
+const USAGE: &str = "Usage: partner [--json | --inline]
+       partner -p [PROMPT] [--output-format text|json|stream-json]
+
+  -p, --print [PROMPT]    run one task and exit, the prompt is read from stdin if missing
+  --output-format FORMAT  the output of -p: text (default), json or stream-json
+  --json                  talk newline-delimited JSON on stdin and stdout
+  --inline                draw the Terminal UI below the prompt and keep the conversation
+                          in the terminal scrollback
+  -h, --help              show this help";
+
+const PREAMBLE: &str = "You are a coding partner working in the user's project. \
+Think step by step, use the available tools to inspect and change the project, \
+and finish with a concise answer.";
+
+/// The command line options.
+#[derive(Debug, Default)]
+struct Options {
+    /// `-p`, the prompt of a one-shot task, empty if it is read from stdin
+    print: Option<String>,
+    output_format: Option<OutputFormat>,
+    json: bool,
+    inline: bool,
+    help: bool,
+}
+
+impl Options {
+    /// Parses the arguments in any order, the prompt of `-p` is the only positional one.
+    fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let mut options = Options::default();
+        let mut print = false;
+        let mut prompt = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-p" | "--print" => print = true,
+                "--output-format" => {
+                    let format = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--output-format needs a value"))?;
+                    options.output_format = Some(format.parse()?);
+                }
+                "--json" => options.json = true,
+                "--inline" => options.inline = true,
+                "-h" | "--help" => options.help = true,
+                _ if !arg.starts_with('-') && prompt.is_none() => prompt = Some(arg),
+                _ => anyhow::bail!("Unknown argument: {}\n\n{}", arg, USAGE),
+            }
+        }
+        if print {
+            options.print = Some(prompt.unwrap_or_default());
+        } else if let Some(prompt) = prompt {
+            anyhow::bail!("Unknown argument: {}\n\n{}", prompt, USAGE);
+        }
+        if options.output_format.is_some() && options.print.is_none() {
+            anyhow::bail!("--output-format needs -p\n\n{}", USAGE);
+        }
+        Ok(options)
+    }
+}
+
+/// Chooses the JSON-lines console with `--json`, otherwise the Terminal UI when both stdin
+/// and stdout are a TTY and plain StdIo if not. The Terminal UI is drawn inline with
+/// `inline`, instead of on the alternate screen.
+///
+/// The Terminal and StdIo keep the prompt history in the `.partner/history` file of the
+/// project.
+fn open_console(json: bool, inline: bool) -> anyhow::Result<Box<dyn Console + Send>> {
+    if json {
+        return Ok(Box::new(JsonLines::new()));
+    }
+    let history = History::load(&History::default_path(&std::env::current_dir()?))?;
+    if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        let terminal = if inline {
+            Terminal::inline(INLINE_HEIGHT)?
+        } else {
+            Terminal::new()?
+        };
+        Ok(Box::new(terminal.with_history(history)))
+    } else {
+        Ok(Box::new(StdIo::new().with_history(history)))
+    }
+}
+
+/// Adds the commands handled by the REPL itself and the project and user prompt commands.
+///
+/// Returns the warnings about the prompt command files that were skipped.
+fn register_commands(console: &mut (dyn Console + Send)) -> anyhow::Result<Vec<String>> {
+    console.commands().register(CommandSpec::new(
+        "clear",
+        "Forget the conversation and start over",
+    ));
+    console
+        .commands()
+        .register(CommandSpec::new("model", "Choose the model"));
+    console.commands().register(CommandSpec::new(
+        "cost",
+        "Show the tokens and the cost of the session",
+    ));
+    let dirs = custom::default_dirs(&std::env::current_dir()?);
+    let (_, warnings) = custom::register_dirs(console.commands(), &dirs);
+    Ok(warnings)
+}
+
+/// Shows a short message outside of an agent turn.
+async fn notify(console: &mut (dyn Console + Send), text: String) -> Result<(), ConsoleError> {
+    console.start_responding().await?;
+    console.add_response_text(text).await?;
+    console.stop_responding().await
+}
+
+/// Lets the user pick one of `MODELS` and switches the agent to it.
+async fn choose_model(
+    agent: &mut Agent,
+    console: &mut (dyn Console + Send),
+) -> Result<(), ConsoleError> {
+    let choices = MODELS
+        .iter()
+        .map(|(provider, model)| {
+            let key_var = provider::PROVIDERS
+                .iter()
+                .find(|(name, _)| name == provider)
+                .map_or("", |(_, key_var)| key_var);
+            Choice::new(*model).with_description(format!("{}, needs {}", provider, key_var))
+        })
+        .collect();
+    let index = match console
+        .select("Choose the model:".to_string(), choices, false)
+        .await
+    {
+        Ok(selection) => selection[0],
+        Err(ConsoleError::Cancelled) => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    let (provider, model) = MODELS[index];
+    match provider::connect(provider, model) {
+        Ok(connected) => {
+            agent.set_model(connected);
+            console
+                .add_response_text(format!("Using {} from {}.", model, provider))
+                .await
+        }
+        Err(err) => console.add_message(Message::Error(err.to_string())).await,
+    }
+}
+
+/// Runs the prompt -> respond loop until the user exits or the console is closed.
+///
+/// The status is refreshed before every prompt, as the turns may switch the git branch or
+/// change the working tree.
+async fn repl(agent: &mut Agent, console: &mut (dyn Console + Send)) -> anyhow::Result<()> {
+    let dir = std::env::current_dir()?;
+    loop {
+        agent.update_status(console);
+        console.status().refresh_workspace(&dir);
+        let input = match console.prompt_input().await {
+            Ok(input) => input,
+            Err(ConsoleError::Terminated) => return Ok(()),
+            Err(ConsoleError::UnknownCommand { command }) => {
+                let text = match console.commands().get(&command) {
+                    Some(spec) => format!("Usage: {}", spec.usage()),
+                    None => format!("Unknown command: {}, see /help", command),
+                };
+                notify(console, text).await?;
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        match input {
+            ConsoleInput::Exit => return Ok(()),
+            ConsoleInput::Thinking(thinking) => {
+                let current = console.observability(None);
+                console.observability(Some(Observability {
+                    thinking,
+                    ..current
+                }));
+            }
+            ConsoleInput::Statistics(statistics) => {
+                let current = console.observability(None);
+                console.observability(Some(Observability {
+                    statistics,
+                    ..current
+                }));
+            }
+            ConsoleInput::Command { name, .. } if name == "clear" => {
+                agent.clear_history();
+                notify(console, "The conversation is cleared.".to_string()).await?;
+            }
+            ConsoleInput::Command { name, .. } if name == "model" => {
+                console.start_responding().await?;
+                choose_model(agent, console).await?;
+                console.stop_responding().await?;
+            }
+            ConsoleInput::Command { name, .. } if name == "cost" => {
+                let statistics = agent.session_statistics().to_string();
+                notify(console, statistics).await?;
+            }
+            ConsoleInput::Help => {
+                let help = console.commands().help();
+                notify(console, help).await?;
+            }
+            ConsoleInput::Status => {
+                let status = console.status().line(ConsoleMode::Prompting);
+                notify(console, status).await?;
+            }
+            ConsoleInput::Command { name, .. } | ConsoleInput::UnknownCommand { command: name } => {
+                notify(
+                    console,
+                    format!("Unknown command: /{}", name.trim_start_matches('/')),
+                )
+                .await?;
+            }
+            ConsoleInput::Prompt { prompt } => {
+                console.start_responding().await?;
+                match agent.run_turn(console, prompt).await {
+                    Ok(_) => {}
+                    Err(AgentError::Console(ConsoleError::Terminated)) => return Ok(()),
+                    Err(AgentError::Console(err)) => return Err(err.into()),
+                    Err(err) => console.add_message(Message::Error(err.to_string())).await?,
+                }
+                console.stop_responding().await?;
+            }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv::dotenv().ok();
+    let options = Options::parse(std::env::args().skip(1))?;
+    if options.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let provider =
+        std::env::var("PARTNER_PROVIDER").unwrap_or_else(|_| DEFAULT_PROVIDER.to_string());
+    let model = std::env::var("PARTNER_MODEL").unwrap_or_else(|_| DEFAULT_MODEL.to_string());
+
+    let model = provider::connect(&provider, &model)?;
+    let dir = std::env::current_dir()?;
+    let permissions = Permissions::load(&Permissions::default_path(&dir))?;
+    let mut agent = Agent::new(model)
+        .with_preamble(PREAMBLE)
+        .with_tools(builtin::project_tools(&dir))
+        .with_permissions(permissions);
+    if let Some(mut prompt) = options.print {
+        if prompt.is_empty() {
+            std::io::stdin().read_to_string(&mut prompt)?;
+        }
+        let prompt = prompt.trim();
+        if prompt.is_empty() {
+            anyhow::bail!("The prompt of -p is empty");
+        }
+        let format = options.output_format.unwrap_or_default();
+        let code = oneshot::run(&mut agent, prompt, format).await?;
+        std::process::exit(code);
+    }
+    let mut console = open_console(options.json, options.inline)?;
+    let warnings = register_commands(console.as_mut())?;
+    if !warnings.is_empty() {
+        console.start_responding().await?;
+        for warning in warnings {
+            console.add_message(Message::Warning(warning)).await?;
+        }
+        console.stop_responding().await?;
+    }
+    repl(&mut agent, console.as_mut()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Options> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_in_any_order() {
+        for args in [
+            &["-p", "fix the tests", "--output-format", "json"][..],
+            &["-p", "--output-format", "json", "fix the tests"],
+            &["--output-format", "json", "fix the tests", "--print"],
+        ] {
+            let options = parse(args).unwrap();
+            assert_eq!(
+                options.print.as_deref(),
+                Some("fix the tests"),
+                "{:?}",
+                args
+            );
+            assert_eq!(
+                options.output_format,
+                Some(OutputFormat::Json),
+                "{:?}",
+                args
+            );
+        }
+
+        let options = parse(&["--inline", "-p"]).unwrap();
+        assert_eq!(options.print.as_deref(), Some(""));
+        assert!(options.inline);
+        let options = parse(&["--json"]).unwrap();
+        assert!(options.json && options.print.is_none());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for args in [
+            &["fix the tests"][..],
+            &["-p", "fix", "the tests"],
+            &["-p", "--verbose"],
+            &["--output-format", "json"],
+            &["-p", "--output-format"],
+            &["-p", "--output-format", "yaml"],
+        ] {
+            assert!(parse(args).is_err(), "{:?}", args);
+        }
+    }
 }
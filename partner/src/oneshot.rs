@@ -0,0 +1,244 @@
+//! The non-interactive mode, `partner -p "prompt"`, running a single task without a REPL.
+
+use crate::register_commands;
+use agent::react::{Agent, AgentError, StopReason, TurnReport};
+use console::common::{Console, ConsoleInput, Message};
+use console::json::JsonLines;
+use console::stdio::StdIo;
+use serde_json::{Value, json};
+use std::str::FromStr;
+use std::time::Instant;
+
+/// The exit code of a task that failed or didn't finish.
+pub const EXIT_FAILED: i32 = 1;
+
+/// The exit code of a task stopped by an action that needs an approval, since nobody can
+/// answer the permission request. Approvals are pre-granted in `.partner/permissions`.
+pub const EXIT_NEEDS_APPROVAL: i32 = 2;
+
+/// How the output of a one-shot task is written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// the human-readable output of `StdIo`
+    #[default]
+    Text,
+    /// a single JSON object with the result and all the console events, once the task ends
+    Json,
+    /// the console events as JSON lines while the task runs, followed by the result
+    StreamJson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "stream-json" => Ok(Self::StreamJson),
+            _ => anyhow::bail!(
+                "Unknown output format: {}, expected text, json or stream-json",
+                text
+            ),
+        }
+    }
+}
+
+/// The report of a task, if it finished, and the exit code and the error of a failed one.
+type Outcome = (Option<TurnReport>, Option<(i32, String)>);
+
+/// Runs one task and returns the exit code of the process.
+///
+/// The prompt may also be a custom prompt command like `/review src`. Questions to the user
+/// can't be answered, the console has no input, so a permission request ends
+/// the task with `EXIT_NEEDS_APPROVAL`.
+pub async fn run(agent: &mut Agent, prompt: &str, format: OutputFormat) -> anyhow::Result<i32> {
+    let mut events = Vec::new();
+    let mut console: Box<dyn Console + Send + '_> = match format {
+        OutputFormat::Text => Box::new(StdIo::write_only(tokio::io::stdout())),
+        OutputFormat::Json => Box::new(JsonLines::write_only(&mut events)),
+        OutputFormat::StreamJson => Box::new(JsonLines::write_only(tokio::io::stdout())),
+    };
+    let started = Instant::now();
+    let (report, error) = run_task(agent, console.as_mut(), prompt).await?;
+    drop(console);
+
+    let code = error.as_ref().map_or(0, |(code, _)| *code);
+    let mut result = json!({
+        "type": "result",
+        "success": code == 0,
+        "answer": report.as_ref().and_then(|report| report.answer.clone()),
+        "error": error.as_ref().map(|(_, error)| error),
+        "steps": report.as_ref().map(|report| report.steps),
+        "statistics": report.as_ref().map(|report| &report.statistics),
+        "duration_ms": started.elapsed().as_millis(),
+    });
+    match format {
+        OutputFormat::Text => {
+            if let Some((_, error)) = &error {
+                eprintln!("partner: {}", error);
+            }
+        }
+        OutputFormat::Json => {
+            let events: Vec<Value> = String::from_utf8_lossy(&events)
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect();
+            result["events"] = Value::Array(events);
+            println!("{}", result);
+        }
+        OutputFormat::StreamJson => println!("{}", result),
+    }
+    Ok(code)
+}
+
+/// Runs the task of `prompt` on `console`, only a denied approval gets
+/// `EXIT_NEEDS_APPROVAL`, every other failure `EXIT_FAILED`.
+async fn run_task(
+    agent: &mut Agent,
+    console: &mut (dyn Console + Send),
+    prompt: &str,
+) -> anyhow::Result<Outcome> {
+    let warnings = register_commands(console)?;
+    let prompt = match console.commands().parse(prompt) {
+        Ok(ConsoleInput::Prompt { prompt }) => prompt,
+        _ => anyhow::bail!(
+            "Only prompts and prompt commands can run with -p: {}",
+            prompt
+        ),
+    };
+
+    console.start_responding().await?;
+    for warning in warnings {
+        console.add_message(Message::Warning(warning)).await?;
+    }
+    let outcome = match agent.run_turn(console, prompt).await {
+        Ok(report) => {
+            let error = match report.stop {
+                StopReason::FinalAnswer => None,
+                StopReason::StepLimit => Some("the task was stopped at the step limit"),
+                StopReason::Cancelled => Some("the task was cancelled"),
+            };
+            (
+                Some(report),
+                error.map(|error| (EXIT_FAILED, error.to_string())),
+            )
+        }
+        Err(err @ AgentError::ApprovalNeeded { .. }) => (
+            None,
+            Some((
+                EXIT_NEEDS_APPROVAL,
+                format!(
+                    "{}, allow the action in .partner/permissions or run partner interactively",
+                    err
+                ),
+            )),
+        ),
+        Err(AgentError::Console(err)) => (None, Some((EXIT_FAILED, err.to_string()))),
+        Err(err) => {
+            console.add_message(Message::Error(err.to_string())).await?;
+            (None, Some((EXIT_FAILED, err.to_string())))
+        }
+    };
+    console.stop_responding().await?;
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent::builtin::Shell;
+    use agent::model::{LanguageModel, ModelRequest, ModelResponse, ToolCall};
+    use async_trait::async_trait;
+    use console::scripted::ScriptedConsole;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    /// A model replaying a fixed list of responses.
+    struct ScriptedModel(Mutex<VecDeque<ModelResponse>>);
+
+    #[async_trait]
+    impl LanguageModel for ScriptedModel {
+        async fn complete(&self, _request: ModelRequest<'_>) -> Result<ModelResponse, AgentError> {
+            self.0
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or_else(|| AgentError::Model("no more responses".to_string()))
+        }
+    }
+
+    fn scripted_agent(root: &std::path::Path, responses: Vec<ModelResponse>) -> Agent {
+        Agent::new(Arc::new(ScriptedModel(Mutex::new(responses.into()))))
+            .with_tool(Shell::new(root))
+    }
+
+    fn touch() -> ModelResponse {
+        ModelResponse {
+            tool_calls: vec![ToolCall {
+                id: "1".to_string(),
+                name: "shell".to_string(),
+                arguments: json!({"command": "touch created"}),
+            }],
+            ..ModelResponse::default()
+        }
+    }
+
+    fn code((_, error): &Outcome) -> i32 {
+        error.as_ref().map_or(0, |(code, _)| *code)
+    }
+
+    #[tokio::test]
+    async fn test_final_answer() {
+        let dir = tempfile::tempdir().unwrap();
+        let answer = ModelResponse {
+            text: Some("Done.".to_string()),
+            ..ModelResponse::default()
+        };
+        let mut agent = scripted_agent(dir.path(), vec![answer]);
+        let mut console = ScriptedConsole::new([]);
+
+        let outcome = run_task(&mut agent, &mut console, "hello").await.unwrap();
+        assert_eq!(code(&outcome), 0);
+        assert_eq!(outcome.0.unwrap().answer.as_deref(), Some("Done."));
+    }
+
+    #[tokio::test]
+    async fn test_approval_needed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut agent = scripted_agent(dir.path(), vec![touch()]);
+        let mut console = StdIo::write_only(Vec::new());
+
+        let (report, error) = run_task(&mut agent, &mut console, "create a file")
+            .await
+            .unwrap();
+        assert!(report.is_none());
+        let (code, error) = error.unwrap();
+        assert_eq!(code, EXIT_NEEDS_APPROVAL);
+        assert!(
+            error.starts_with("shell needs an approval for: touch created"),
+            "{}",
+            error
+        );
+        assert!(!dir.path().join("created").exists());
+    }
+
+    #[tokio::test]
+    async fn test_other_failures() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // a console that ends is not a denied approval
+        let mut agent = scripted_agent(dir.path(), vec![touch()]);
+        let mut console = ScriptedConsole::new([]);
+        let outcome = run_task(&mut agent, &mut console, "create a file")
+            .await
+            .unwrap();
+        assert_eq!(code(&outcome), EXIT_FAILED);
+
+        let mut agent = scripted_agent(dir.path(), vec![]);
+        let mut console = StdIo::write_only(Vec::new());
+        let outcome = run_task(&mut agent, &mut console, "hello").await.unwrap();
+        assert_eq!(code(&outcome), EXIT_FAILED);
+        assert_eq!(outcome.1.unwrap().1, "Model error: no more responses");
+    }
+}